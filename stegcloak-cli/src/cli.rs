@@ -0,0 +1,207 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+/// Hide secrets inside text with invisible unicode characters
+#[derive(Debug, Parser)]
+#[command(name = "stegcloak", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Hide a secret inside cover text
+    Hide(HideArgs),
+    /// Reveal a secret hidden inside text
+    Reveal(RevealArgs),
+    /// Report hidden data in text without revealing it
+    Detect(InputArgs),
+    /// Remove hidden data from text, leaving only the cover text
+    Strip(InputArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct HideArgs {
+    /// The secret to hide
+    #[arg(short, long, conflicts_with = "secret_file")]
+    pub secret: Option<String>,
+
+    /// Read the secret from a file, or `-` for stdin
+    #[arg(long, value_name = "PATH", required_unless_present = "secret")]
+    pub secret_file: Option<PathBuf>,
+
+    /// Read the cover text from a file, or `-` for stdin
+    #[arg(short, long, value_name = "PATH", default_value = "-")]
+    pub cover: PathBuf,
+
+    /// Hide the secret without encrypting it
+    #[arg(long, conflicts_with_all = ["prompt", "password_env", "password_fd", "integrity"])]
+    pub plaintext: bool,
+
+    /// Protect the secret against tampering
+    #[arg(long)]
+    pub integrity: bool,
+
+    /// How the secret is mapped onto invisible characters
+    #[arg(long, value_enum, default_value_t = Codec::Base4)]
+    pub codec: Codec,
+
+    /// Invisible characters to hide the secret with, pick one the target platform keeps
+    #[arg(long, value_enum, default_value_t = Profile::MaxCompatibility)]
+    pub alphabet: Profile,
+
+    /// Where the secret goes in the cover text
+    #[arg(long, value_enum, default_value_t = Placement::FirstSpace)]
+    pub placement: Placement,
+
+    /// Add error correction, with parity of up to 100% of the data, to survive changed chars
+    #[arg(long, value_name = "PERCENT", default_value_t = 0)]
+    pub redundancy: u8,
+
+    /// Fail instead of hiding when the output would be longer than this
+    #[arg(long, value_name = "LENGTH")]
+    pub max_length: Option<usize>,
+
+    /// Unit `--max-length` counts in, whichever the target platform counts
+    #[arg(long, value_enum, default_value_t = LengthUnit::Chars, requires = "max_length")]
+    pub length_unit: LengthUnit,
+
+    #[command(flatten)]
+    pub password: PasswordArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct RevealArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Reveal every secret the password opens, one per line
+    #[arg(long)]
+    pub all: bool,
+
+    /// Refuse secrets which decompress to more bytes than this, unless streamed
+    #[arg(long, value_name = "BYTES", default_value_t = stegcloak::compact::MAX_SIZE)]
+    pub max_size: usize,
+
+    #[command(flatten)]
+    pub password: PasswordArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct InputArgs {
+    /// Read the text from a file, or `-` for stdin
+    #[arg(value_name = "PATH", default_value = "-")]
+    pub input: PathBuf,
+}
+
+/// Where the password is read from
+#[derive(Debug, Args)]
+pub struct PasswordArgs {
+    /// Prompt for the password on the terminal
+    #[arg(short, long, conflicts_with_all = ["password_env", "password_fd"])]
+    pub prompt: bool,
+
+    /// Read the password from an environment variable
+    #[arg(long, value_name = "VAR", conflicts_with = "password_fd")]
+    pub password_env: Option<String>,
+
+    /// Read the password from an open file descriptor
+    #[arg(long, value_name = "FD")]
+    pub password_fd: Option<u32>,
+}
+
+/// Built-in alphabets, revealing detects them on its own
+#[derive(Debug, Copy, Clone, ValueEnum)]
+pub enum Profile {
+    MaxCompatibility,
+    JoinersOnly,
+    VariationSelectors,
+    TagCharacters,
+}
+
+impl From<Profile> for stegcloak::codec::Profile {
+    fn from(profile: Profile) -> Self {
+        match profile {
+            Profile::MaxCompatibility => Self::MaxCompatibility,
+            Profile::JoinersOnly => Self::JoinersOnly,
+            Profile::VariationSelectors => Self::VariationSelectors,
+            Profile::TagCharacters => Self::TagCharacters,
+        }
+    }
+}
+
+/// Placements, revealing gathers fragments on its own
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Placement {
+    FirstSpace,
+    LastSpace,
+    /// Appended to the end of the cover text
+    End,
+    /// In fragments after every space
+    Spread,
+    /// In fragments after spaces picked with the password as the key, or at random without one
+    Scattered,
+}
+
+impl From<Placement> for stegcloak::Placement {
+    fn from(placement: Placement) -> Self {
+        match placement {
+            Placement::FirstSpace => Self::FirstSpace,
+            Placement::LastSpace => Self::LastSpace,
+            Placement::End => Self::End,
+            Placement::Spread => Self::Spread,
+            Placement::Scattered => Self::Scattered,
+        }
+    }
+}
+
+/// Codecs, revealing detects them on its own
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Codec {
+    Base4,
+    Radix,
+    /// One variation selector per byte, attached to the word before the space
+    VariationSelectors,
+    /// 7 bits per tag character
+    TagCharacters,
+    /// 3 bits in each space of the cover text, which needs plenty of them
+    Whitespace,
+    /// 1 bit in each Latin letter with a Cyrillic or Greek lookalike
+    Homoglyphs,
+}
+
+impl From<Codec> for stegcloak::codec::Codec {
+    fn from(codec: Codec) -> Self {
+        match codec {
+            Codec::Base4 => Self::Base4,
+            Codec::Radix => Self::Radix,
+            Codec::VariationSelectors => Self::VariationSelectors,
+            Codec::TagCharacters => Self::TagCharacters,
+            Codec::Whitespace => Self::Whitespace,
+            Codec::Homoglyphs => Self::Homoglyphs,
+        }
+    }
+}
+
+/// Units a length limit can count in
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum LengthUnit {
+    /// Unicode code points
+    Chars,
+    /// UTF-8 bytes
+    Utf8,
+    /// UTF-16 code units, like JavaScript counts
+    Utf16,
+}
+
+impl LengthUnit {
+    pub fn limit(&self, max: usize) -> stegcloak::Limit {
+        match self {
+            LengthUnit::Chars => stegcloak::Limit::Chars(max),
+            LengthUnit::Utf8 => stegcloak::Limit::Utf8(max),
+            LengthUnit::Utf16 => stegcloak::Limit::Utf16(max),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use stegcloak::{codec::CodecError, crypto::DeEncryptError, StegError};
+
+/// Success
+pub const OK: u8 = 0;
+/// Any error not covered by a more specific code
+pub const FAILURE: u8 = 1;
+// 2 is used by clap for usage errors
+/// No hidden data was found
+pub const NOT_FOUND: u8 = 3;
+/// A password or identity is required, but wasn't given
+pub const PASSWORD_REQUIRED: u8 = 4;
+/// The password doesn't open the secret
+pub const WRONG_PASSWORD: u8 = 5;
+/// The hidden data is corrupted or was tampered with
+pub const CORRUPTED: u8 = 6;
+/// The cover text can't carry the secret
+pub const NO_SPACE: u8 = 7;
+
+/// Exit code scripts can branch on for an error
+pub fn code(error: &StegError) -> u8 {
+    match error {
+        StegError::CodecError(CodecError::ZwcDataNotfound) => NOT_FOUND,
+
+        StegError::SpaceRequired
+        | StegError::LimitExceeded { .. }
+        | StegError::CodecError(CodecError::NotEnoughSpaces | CodecError::NotEnoughLetters) => {
+            NO_SPACE
+        }
+
+        StegError::PasswordRequired | StegError::IdentityRequired => PASSWORD_REQUIRED,
+
+        StegError::DeEncryptError(
+            DeEncryptError::IncorrectPassword | DeEncryptError::NoMatchingIdentity,
+        ) => WRONG_PASSWORD,
+
+        StegError::CodecError(_)
+        | StegError::HeaderError(_)
+        | StegError::FecError(_)
+        | StegError::DeCompressError(_)
+        | StegError::DeEncryptError(_) => CORRUPTED,
+
+        _ => FAILURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use stegcloak::fec::FecError;
+
+    use super::*;
+
+    #[test]
+    fn test_codes() {
+        assert_eq!(code(&StegError::SpaceRequired), NO_SPACE);
+        assert_eq!(code(&StegError::PasswordRequired), PASSWORD_REQUIRED);
+        assert_eq!(
+            code(&DeEncryptError::IncorrectPassword.into()),
+            WRONG_PASSWORD
+        );
+        assert_eq!(code(&DeEncryptError::IntegrityError.into()), CORRUPTED);
+        assert_eq!(code(&CodecError::ZwcDataNotfound.into()), NOT_FOUND);
+        assert_eq!(code(&CodecError::MalformedData.into()), CORRUPTED);
+        assert_eq!(code(&FecError::TooManyErrors.into()), CORRUPTED);
+        assert_eq!(code(&CodecError::NotEnoughSpaces.into()), NO_SPACE);
+        assert_eq!(code(&CodecError::NotEnoughLetters.into()), NO_SPACE);
+
+        let limit = stegcloak::Limit::Chars(280);
+        let error = StegError::LimitExceeded { length: 300, limit };
+        assert_eq!(code(&error), NO_SPACE);
+    }
+}
@@ -0,0 +1,189 @@
+mod cli;
+mod exit;
+
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+use clap::Parser;
+use stegcloak::{codec::Codec, StegCloak, StegError};
+use zeroize::Zeroizing;
+
+use cli::{Cli, Command, HideArgs, InputArgs, PasswordArgs, Placement, RevealArgs};
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("{0}")]
+    Steg(#[from] StegError),
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("Environment variable {0} is not set")]
+    MissingEnv(String),
+}
+
+impl Error {
+    fn code(&self) -> u8 {
+        match self {
+            Error::Steg(e) => exit::code(e),
+            Error::Io(_) | Error::MissingEnv(_) => exit::FAILURE,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Hide(args) => hide(args),
+        Command::Reveal(args) => reveal(args),
+        Command::Detect(args) => detect(args),
+        Command::Strip(args) => strip(args),
+    };
+
+    match result {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(e.code())
+        }
+    }
+}
+
+fn hide(args: HideArgs) -> Result<u8, Error> {
+    let mut secret: Box<dyn Read> = match (args.secret, args.secret_file) {
+        (Some(secret), _) => Box::new(io::Cursor::new(secret)),
+        (None, Some(path)) => open(&path)?,
+        (None, None) => unreachable!("clap requires one of them"),
+    };
+
+    let cover = read_string(&args.cover)?;
+
+    let password = match password(&args.password)? {
+        Some(password) => Some(password),
+        None if args.plaintext => None,
+        None => Some(Zeroizing::new(rpassword::prompt_password("Password: ")?)),
+    };
+
+    let codec = Codec::from(args.codec);
+
+    let mut steg = StegCloak::new()
+        .integrity(args.integrity)
+        .codec(codec)
+        .alphabet(stegcloak::codec::Profile::from(args.alphabet))
+        .placement(args.placement.into())
+        .redundancy(args.redundancy);
+
+    if let Some(max) = args.max_length {
+        steg = steg.hide_within(args.length_unit.limit(max));
+    }
+
+    // these replace chars of the cover text, split the stream, need all of it for parity or
+    // its length, so they can't be streamed
+    let fragmented = matches!(args.placement, Placement::Spread | Placement::Scattered);
+    if codec.substitutes() || fragmented || args.redundancy > 0 || args.max_length.is_some() {
+        let mut data = Zeroizing::new(Vec::new());
+        secret.read_to_end(&mut data)?;
+
+        let hidden = steg.hide_bytes(&*data, password.as_deref().map(String::as_str), cover)?;
+        io::stdout().write_all(hidden.as_bytes())?;
+    } else {
+        let password = password.as_deref().map(String::as_str);
+        steg.hide_stream(secret, password, cover, io::stdout().lock())?;
+    }
+
+    Ok(exit::OK)
+}
+
+fn reveal(args: RevealArgs) -> Result<u8, Error> {
+    let password = password(&args.password)?;
+    let password = password.as_deref().map(String::as_str);
+
+    let steg = StegCloak::new().max_size(args.max_size);
+    let mut stdout = io::stdout().lock();
+
+    if args.all {
+        let message = read_string(&args.input.input)?;
+        for secret in steg.reveal_all_bytes(&message, password)? {
+            stdout.write_all(&secret)?;
+            stdout.write_all(b"\n")?;
+        }
+    } else {
+        let message = open(&args.input.input)?;
+        steg.reveal_stream(message, password, stdout)?;
+    }
+
+    Ok(exit::OK)
+}
+
+fn detect(args: InputArgs) -> Result<u8, Error> {
+    let text = read_string(&args.input)?;
+    let detections = stegcloak::detect(&text);
+
+    let mut stdout = io::stdout().lock();
+    for detection in &detections {
+        let kind = if detection.encrypted {
+            "encrypted"
+        } else {
+            "plaintext"
+        };
+
+        writeln!(
+            stdout,
+            "{}..{}\t{} bytes\t{kind}",
+            detection.range.start, detection.range.end, detection.payload_size
+        )?;
+    }
+
+    if detections.is_empty() {
+        Ok(exit::NOT_FOUND)
+    } else {
+        Ok(exit::OK)
+    }
+}
+
+fn strip(args: InputArgs) -> Result<u8, Error> {
+    let text = read_string(&args.input)?;
+    io::stdout().write_all(stegcloak::strip(&text).as_bytes())?;
+
+    Ok(exit::OK)
+}
+
+/// Open a file, or stdin if the path is `-`
+fn open(path: &Path) -> io::Result<Box<dyn Read>> {
+    if path == Path::new("-") {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(File::open(path)?))
+    }
+}
+
+fn read_string(path: &Path) -> io::Result<String> {
+    let mut data = String::new();
+    open(path)?.read_to_string(&mut data)?;
+
+    Ok(data)
+}
+
+/// Read the password from wherever the arguments say, or `None` if no source was given
+///
+/// The password is wiped from memory once dropped
+fn password(args: &PasswordArgs) -> Result<Option<Zeroizing<String>>, Error> {
+    let password = if args.prompt {
+        rpassword::prompt_password("Password: ")?
+    } else if let Some(var) = &args.password_env {
+        env::var(var).map_err(|_| Error::MissingEnv(var.clone()))?
+    } else if let Some(fd) = args.password_fd {
+        let password = Zeroizing::new(fs::read_to_string(format!("/dev/fd/{fd}"))?);
+        // only the line ending, the password itself may end in whitespace
+        let password = password.strip_suffix('\n').unwrap_or(&password);
+        password.strip_suffix('\r').unwrap_or(password).to_owned()
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(Zeroizing::new(password)))
+}
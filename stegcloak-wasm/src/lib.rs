@@ -1,17 +1,39 @@
+use stegcloak::crypto::recipient::{Identity, Recipient};
 use stegcloak::encrypt;
 use stegcloak::plaintext;
+use stegcloak::recipient;
 use wasm_bindgen::prelude::*;
 
+#[wasm_bindgen]
+pub fn reveal(message: &str, password: Option<String>) -> Result<String, JsError> {
+    Ok(stegcloak::reveal(message, password.as_deref())?)
+}
+
+#[wasm_bindgen]
+pub fn reveal_bytes(message: &str, password: Option<String>) -> Result<Vec<u8>, JsError> {
+    Ok(stegcloak::reveal_bytes(message, password.as_deref())?)
+}
+
 #[wasm_bindgen]
 pub fn plaintext_hide(secret: &str, message: &str) -> Result<String, JsError> {
     Ok(plaintext::hide(secret, message)?)
 }
 
+#[wasm_bindgen]
+pub fn plaintext_hide_bytes(secret: &[u8], message: &str) -> Result<String, JsError> {
+    Ok(plaintext::hide_bytes(secret, message)?)
+}
+
 #[wasm_bindgen]
 pub fn plaintext_reveal(message: &str) -> Result<String, JsError> {
     Ok(plaintext::reveal(message)?)
 }
 
+#[wasm_bindgen]
+pub fn plaintext_reveal_bytes(message: &str) -> Result<Vec<u8>, JsError> {
+    Ok(plaintext::reveal_bytes(message)?)
+}
+
 #[wasm_bindgen]
 pub fn encrypt_hide(
     secret: &str,
@@ -22,7 +44,55 @@ pub fn encrypt_hide(
     Ok(encrypt::hide(secret, password, integrity, message)?)
 }
 
+#[wasm_bindgen]
+pub fn encrypt_hide_bytes(
+    secret: &[u8],
+    password: &str,
+    integrity: bool,
+    message: &str,
+) -> Result<String, JsError> {
+    Ok(encrypt::hide_bytes(secret, password, integrity, message)?)
+}
+
 #[wasm_bindgen]
 pub fn encrypt_reveal(password: &str, message: &str) -> Result<String, JsError> {
     Ok(encrypt::reveal(password, message)?)
 }
+
+#[wasm_bindgen]
+pub fn encrypt_reveal_bytes(password: &str, message: &str) -> Result<Vec<u8>, JsError> {
+    Ok(encrypt::reveal_bytes(password, message)?)
+}
+
+/// Generate a new identity, returned as text
+#[wasm_bindgen]
+pub fn generate_identity() -> String {
+    Identity::generate().to_string()
+}
+
+/// Public key of a text identity
+#[wasm_bindgen]
+pub fn identity_to_public(identity: &str) -> Result<String, JsError> {
+    Ok(identity.parse::<Identity>()?.to_public().to_string())
+}
+
+#[wasm_bindgen]
+pub fn recipient_hide(
+    secret: &str,
+    recipients: Vec<String>,
+    message: &str,
+) -> Result<String, JsError> {
+    let recipients = recipients
+        .iter()
+        .map(|r| r.parse::<Recipient>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(recipient::hide(secret, &recipients, message)?)
+}
+
+#[wasm_bindgen]
+pub fn recipient_reveal(identity: &str, message: &str) -> Result<String, JsError> {
+    let identity = identity.parse::<Identity>()?;
+
+    Ok(recipient::reveal_with_identity(&identity, message)?)
+}
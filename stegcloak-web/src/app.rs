@@ -6,10 +6,7 @@ use leptos_use::{
     UseColorModeReturn,
 };
 
-use crate::{
-    components::page_base::PageBase,
-    pages::{home::Home, not_found::NotFound},
-};
+use crate::{components::page_base::PageBase, pages::home::Home};
 
 /// An app router which renders the homepage and handles 404's
 #[component]
@@ -83,10 +80,6 @@ pub struct GetTheme {
 }
 
 impl GetTheme {
-    pub fn theme(&self) -> ColorMode {
-        self.mode.get()
-    }
-
     pub fn is_auto(&self) -> bool {
         matches!(self.mode.get(), ColorMode::Auto)
     }
@@ -106,22 +99,6 @@ impl GetTheme {
         self.is_dark_preferred.get()
     }
 
-    pub fn is_dark_theme(&self) -> bool {
-        match self.mode.get() {
-            ColorMode::Auto => self.is_dark_preferred.get(),
-            ColorMode::Light => false,
-            ColorMode::Dark => true,
-            ColorMode::Custom(theme) => match &*theme {
-                "dracula" => true,
-                "night" => true,
-                "dim" => true,
-                "cupcake" => false,
-                "valentine" => false,
-                _ => unreachable!(),
-            },
-        }
-    }
-
     pub fn themes(&self) -> &'static [&'static str] {
         &[
             "light",
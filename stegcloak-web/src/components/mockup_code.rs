@@ -11,17 +11,11 @@ use leptos_meta::Style;
 pub enum Prefix {
     #[default]
     None,
-    // 1
-    // 2
-    // 3
-    // ..
-    Numbered,
     // $
     // >
     // >
     // ..
     UnixShell,
-    Custom(Signal<HashMap<usize, String>>),
 }
 
 #[component]
@@ -32,12 +26,10 @@ pub fn MockupCode(
     #[prop(optional)] mockup_class: String,
 ) -> impl IntoView {
     thread_local! {
-        static INIT: RefCell<bool> = RefCell::new(false);
+        static INIT: RefCell<bool> = const { RefCell::new(false) };
     }
 
-    let numbered = || make_iter((1usize..).map(|n| n.to_string()));
     let unix_shell = || make_iter(UnixShellPrefix::default());
-    let custom = |map| make_iter(CustomPrefix::new(map));
     let none = || make_iter(std::iter::empty());
 
     let no_prefixes = prefixes == Prefix::None;
@@ -47,11 +39,7 @@ pub fn MockupCode(
             {move || {
                 let prefix = Rc::new(RefCell::new(match prefixes {
                     Prefix::None => none(),
-                    Prefix::Numbered => numbered(),
                     Prefix::UnixShell => unix_shell(),
-                    Prefix::Custom(c) => {
-                        custom(c.get())
-                    },
                 }));
 
                 view! {
@@ -135,26 +123,3 @@ impl Iterator for UnixShellPrefix {
         }
     }
 }
-
-#[derive(Debug)]
-struct CustomPrefix {
-    counter: usize,
-    map: HashMap<usize, String>,
-}
-
-impl CustomPrefix {
-    fn new(map: HashMap<usize, String>) -> Self {
-        Self { counter: 0, map }
-    }
-}
-
-impl Iterator for CustomPrefix {
-    type Item = String;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.counter += 1;
-        let c = self.counter;
-
-        self.map.get(&c).map(ToOwned::to_owned)
-    }
-}
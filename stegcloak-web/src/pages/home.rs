@@ -112,63 +112,36 @@ fn Reveal(active_tab: ReadSignal<Tab>) -> impl IntoView {
             return;
         }
 
-        if !password.is_empty() {
-            // try encrypted mode
-            let result = stegcloak::encrypt::reveal(&password, &message);
+        let password = (!password.is_empty()).then_some(&*password);
 
-            if let Err(StegError::DeEncryptError(DeEncryptError::IncorrectPassword)) = result {
+        // the header tells us whether it's encrypted, so a password is only used if needed
+        let data = match stegcloak::reveal(&message, password) {
+            Ok(data) => data,
+            Err(StegError::PasswordRequired) => {
+                password_target
+                    .set_custom_validity("This message is encrypted, try inputting a password");
+                password_target.report_validity();
+                return;
+            }
+            Err(StegError::DeEncryptError(DeEncryptError::IncorrectPassword)) => {
                 password_target.set_custom_validity("Incorrect password");
                 password_target.report_validity();
                 return;
             }
-
-            if let Err(StegError::DeEncryptError(DeEncryptError::IntegrityError)) = result {
+            Err(StegError::DeEncryptError(DeEncryptError::IntegrityError)) => {
                 message_target.set_custom_validity("Message integrity check failed");
                 message_target.report_validity();
                 return;
             }
-
-            if let Err(StegError::DeEncryptError(e)) = result {
-                password_target
-                    .set_custom_validity("This message is not encrypted, try removing this");
-                password_target.report_validity();
-                log::error!("Failed decryption: {e:?}");
-                return;
-            }
-
-            let Ok(data) = result else {
+            Err(e) => {
                 message_target.set_custom_validity("Message is corrupted");
                 message_target.report_validity();
-                log::error!("Failed decryption: {:?}", result.unwrap_err());
+                log::error!("Failed reveal: {e:?}");
                 return;
-            };
-
-            set_secret.set(data);
-        } else {
-            // try plaintext mode
-            let data = match stegcloak::plaintext::reveal(message) {
-                Ok(data) => data,
-                Err(e) => match e {
-                    StegError::DeCompressError(e) => {
-                        message_target.set_custom_validity(
-                            "This is either encrypted or corrupted. Try inputting a password",
-                        );
-                        message_target.report_validity();
-                        log::error!("Failed plaintext decoding: {e:?}");
-                        return;
-                    }
-                    StegError::CodecError(e) => {
-                        message_target.set_custom_validity("Message is corrupted");
-                        message_target.report_validity();
-                        log::error!("Failed plaintext decoding: {e:?}");
-                        return;
-                    }
-                    _ => unreachable!(),
-                },
-            };
+            }
+        };
 
-            set_secret.set(data);
-        }
+        set_secret.set(data);
     };
 
     view! {
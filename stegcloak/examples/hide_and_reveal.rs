@@ -16,4 +16,10 @@ fn main() {
     let hidden = encrypt::hide("mysecret", "password", true, "cover text").unwrap();
     let secret = encrypt::reveal("password", hidden).unwrap();
     assert_eq!("mysecret", secret);
+
+    // binary secrets don't need to be valid utf8
+    let hidden =
+        encrypt::hide_bytes([0xde, 0xad, 0xbe, 0xef], "password", true, "cover text").unwrap();
+    let secret = encrypt::reveal_bytes("password", hidden).unwrap();
+    assert_eq!([0xde, 0xad, 0xbe, 0xef], &*secret);
 }
@@ -0,0 +1,1971 @@
+use std::{
+    io::{self, BufReader, Read, Write},
+    mem,
+};
+
+use hkdf::Hkdf;
+use rand::{
+    rngs::{OsRng, StdRng},
+    SeedableRng as _,
+};
+use secrecy::{ExposeSecret as _, SecretString};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::codec::{self, Alphabet, Codec, CodecError};
+use crate::compact::{self, Compressor, DeCompressError, Dictionary};
+use crate::crypto::{
+    self,
+    recipient::{self, Identity, Recipient},
+    signature::{self, SigningKey, VerifyingKey},
+    stream::{DecryptReader, EncryptWriter},
+    Cipher, DeEncryptError, Kdf, Params,
+};
+use crate::estimate::{Estimate, Limit};
+use crate::fec;
+use crate::header::{self, Compression, Header, HeaderError, Integrity, Mode};
+use crate::StegError;
+
+/// Where the cloaked data is inserted into the cover text
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Placement {
+    /// Right after the first space
+    #[default]
+    FirstSpace,
+    /// Right after the last space
+    LastSpace,
+    /// Appended to the end, which needs no space but only fits a single stream
+    End,
+    /// Split into fragments spread evenly across the spaces, one after each
+    Spread,
+    /// Split into fragments after about half of the spaces, picked pseudo-randomly with the
+    /// password as the key, or at random without one
+    ///
+    /// Revealing doesn't need the key, fragments are gathered in the order they appear. The key
+    /// is expanded from the password with HKDF but without the kdf, so the picks let a guess
+    /// be checked cheaply and are only as hard to guess as the password.
+    Scattered,
+}
+
+impl Placement {
+    /// Byte positions in `message` each of `n` streams can be inserted at, in ascending order
+    ///
+    /// Streams which get split into fragments each get their share of the spaces.
+    fn positions(&self, message: &str, n: usize) -> Option<Vec<Vec<usize>>> {
+        // insert after the space
+        let spaces = message
+            .match_indices(' ')
+            .map(|(p, _)| p + 1)
+            .collect::<Vec<_>>();
+
+        let positions = match self {
+            Placement::FirstSpace => spaces.iter().take(n).map(|&p| vec![p]).collect(),
+            Placement::LastSpace => spaces[spaces.len().saturating_sub(n)..]
+                .iter()
+                .map(|&p| vec![p])
+                .collect(),
+            Placement::End if n == 1 => vec![vec![message.len()]],
+            Placement::End => Vec::new(),
+            Placement::Spread | Placement::Scattered => (0..n)
+                .map(|i| spaces[i * spaces.len() / n..(i + 1) * spaces.len() / n].to_vec())
+                .filter(|share| !share.is_empty())
+                .collect::<Vec<_>>(),
+        };
+
+        (positions.len() == n).then_some(positions)
+    }
+
+    /// Number of fragments a stream with `spaces` positions to pick from gets split into
+    fn fragments(&self, spaces: usize) -> usize {
+        match self {
+            Placement::FirstSpace | Placement::LastSpace | Placement::End => 1,
+            Placement::Spread => spaces,
+            Placement::Scattered => spaces.div_ceil(2),
+        }
+    }
+
+    /// Pick the positions of `n` fragments out of `positions`, in ascending order
+    fn pick(&self, positions: &[usize], n: usize, key: Option<&str>) -> Vec<usize> {
+        if *self != Placement::Scattered {
+            return (0..n).map(|i| positions[i * positions.len() / n]).collect();
+        }
+
+        let mut picked = match key {
+            Some(key) => rand::seq::index::sample(&mut keyed_rng(key), positions.len(), n),
+            None => rand::seq::index::sample(&mut OsRng, positions.len(), n),
+        }
+        .into_vec();
+        picked.sort_unstable();
+
+        picked.into_iter().map(|i| positions[i]).collect()
+    }
+}
+
+/// Rng scattered fragments are picked with, seeded from a key expanded from the password
+fn keyed_rng(password: &str) -> StdRng {
+    // the seed is as good as the password for brute forcing, so it's wiped as well
+    let mut seed = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(b"stegcloak placement"), password.as_bytes())
+        .expand(b"stegcloak scattered", &mut *seed)
+        .expect("32 bytes is a valid hkdf output length");
+
+    StdRng::from_seed(*seed)
+}
+
+/// How a secret gets protected when hiding it
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum Protection<'a> {
+    None,
+    Password(&'a str),
+    Recipients(&'a [Recipient]),
+}
+
+impl<'a> Protection<'a> {
+    fn password(&self) -> Option<&'a str> {
+        match self {
+            Protection::Password(password) => Some(password),
+            Protection::None | Protection::Recipients(_) => None,
+        }
+    }
+}
+
+impl<'a> From<Option<&'a str>> for Protection<'a> {
+    fn from(value: Option<&'a str>) -> Self {
+        value.map_or(Protection::None, Protection::Password)
+    }
+}
+
+/// Key used to reveal a protected secret
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum Key<'a> {
+    Password(&'a str),
+    Identity(&'a Identity),
+}
+
+/// A revealed secret together with the verified key that signed it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signed<T> {
+    pub secret: T,
+    pub signer: VerifyingKey,
+}
+
+/// A revealed secret together with the number of corrupted shards error correction repaired
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Corrected<T> {
+    pub secret: T,
+    pub corrections: usize,
+}
+
+/// A revealed secret, with everything learned about it along the way
+struct Revealed {
+    secret: Vec<u8>,
+    signer: Option<VerifyingKey>,
+    corrections: usize,
+}
+
+/// Configurable hide/reveal
+///
+/// The `encrypt` and `plaintext` modules are presets of this with default settings.
+///
+/// # Examples
+///
+/// ```rust
+///     use stegcloak::{crypto::Kdf, Placement, StegCloak};
+///
+///     let steg = StegCloak::new()
+///         .integrity(true)
+///         .kdf(Kdf::Pbkdf2 { rounds: 20_000 })
+///         .placement(Placement::LastSpace);
+///
+///     let hidden = steg.hide("mysecret", Some("mypassword"), "cover text").unwrap();
+///     let secret = steg.reveal(hidden, Some("mypassword")).unwrap();
+///     assert_eq!(secret, "mysecret");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StegCloak {
+    integrity: bool,
+    kdf: Kdf,
+    cipher: Cipher,
+    compression_level: u32,
+    placement: Placement,
+    alphabet: Option<Alphabet>,
+    codec: Codec,
+    signing_key: Option<SigningKey>,
+    redundancy: u8,
+    limit: Option<Limit>,
+    dictionary: Option<Dictionary>,
+    max_size: usize,
+}
+
+impl Default for StegCloak {
+    fn default() -> Self {
+        Self {
+            integrity: false,
+            kdf: Kdf::default(),
+            cipher: Cipher::default(),
+            compression_level: flate2::Compression::best().level(),
+            placement: Placement::default(),
+            alphabet: None,
+            codec: Codec::default(),
+            signing_key: None,
+            redundancy: 0,
+            limit: None,
+            dictionary: None,
+            max_size: compact::MAX_SIZE,
+        }
+    }
+}
+
+impl StegCloak {
+    /// Create a new instance with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Protect encrypted messages against tampering
+    pub fn integrity(mut self, integrity: bool) -> Self {
+        self.integrity = integrity;
+        self
+    }
+
+    /// Key derivation function used for encrypted messages
+    pub fn kdf(mut self, kdf: Kdf) -> Self {
+        self.kdf = kdf;
+        self
+    }
+
+    /// Cipher used for encrypted messages
+    ///
+    /// Aead ciphers always protect against tampering, regardless of [`StegCloak::integrity`]
+    pub fn cipher(mut self, cipher: Cipher) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// Deflate compression level, 0 (store only) to 9 (best)
+    ///
+    /// Secrets which deflate doesn't shrink are kept as is, or packed as 7 bit ASCII or with a
+    /// [`StegCloak::dictionary`] if shorter, see [`compact::compress_smallest`]
+    pub fn compression_level(mut self, level: u32) -> Self {
+        self.compression_level = level;
+        self
+    }
+
+    /// Custom codebook tried on short secrets along with the built-in [`Dictionary::preset`]
+    ///
+    /// Its id is recorded in the stream, so messages compressed with it only reveal with the same
+    /// dictionary set.
+    pub fn dictionary(mut self, dictionary: Dictionary) -> Self {
+        self.dictionary = Some(dictionary);
+        self
+    }
+
+    /// Largest secret revealing decompresses, and stream it decodes, in bytes
+    ///
+    /// Guards against decompression bombs in untrusted text, defaults to [`compact::MAX_SIZE`].
+    ///
+    /// Note: Secrets [`StegCloak::reveal_stream`] streams to its output are not capped, they
+    ///       don't pile up in memory
+    pub fn max_size(mut self, bytes: usize) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    /// Where the cloaked data is inserted into the cover text
+    pub fn placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Invisible characters the data is encoded with, a [`codec::Profile`] or a custom one
+    ///
+    /// Without it, messages are hidden with [`codec::Profile::MaxCompatibility`] and revealed with
+    /// whichever built-in profile they use.
+    ///
+    /// Note: Messages with a custom alphabet can only be revealed using the same alphabet
+    pub fn alphabet(mut self, alphabet: impl Into<Alphabet>) -> Self {
+        self.alphabet = Some(alphabet.into());
+        self
+    }
+
+    /// How the data is mapped onto the alphabet
+    ///
+    /// The codec is recorded in the stream, so messages reveal regardless of this setting
+    pub fn codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Sign hidden messages so their origin can be verified with [`StegCloak::reveal_signed`]
+    pub fn signing_key(mut self, key: SigningKey) -> Self {
+        self.signing_key = Some(key);
+        self
+    }
+
+    /// Protect hidden messages with Reed-Solomon error correction, 0 (default) for none
+    ///
+    /// Adds `percent` parity shards per 100 data shards of 32 bytes, up to
+    /// [`fec::MAX_REDUNDANCY`], covering the header too. Revealing repairs as many corrupted
+    /// shards as there are parity shards, see [`StegCloak::reveal_corrected`]. With
+    /// [`Codec::Base4`] and [`Codec::Radix`], every shard is encoded on its own, so a dropped or
+    /// added char only corrupts the shard it was in.
+    pub fn redundancy(mut self, percent: u8) -> Self {
+        self.redundancy = percent.min(fec::MAX_REDUNDANCY);
+        self
+    }
+
+    /// Fail with [`StegError::LimitExceeded`] instead of hiding, when the message with the hidden
+    /// secret would be longer than a platform allows
+    ///
+    /// Note: Streaming can't know the length in advance, so [`StegCloak::hide_stream`] refuses
+    ///       to hide with a limit
+    pub fn hide_within(mut self, limit: Limit) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Length hiding a secret adds to any cover text, see [`Estimate`]
+    ///
+    /// Nothing gets encrypted, the length of an encrypted payload is known up front, so no key
+    /// is derived either.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The bytes you want to hide
+    /// * `encrypted` - Whether the secret will be hidden with a password
+    pub fn estimate(&self, secret: impl AsRef<[u8]>, encrypted: bool) -> Estimate {
+        let (compression, secret) =
+            compact::compress_smallest(secret.as_ref(), &self.compressors());
+
+        // the compression id is encrypted along with the secret
+        let (mode, body) = if encrypted {
+            let len = crypto::encrypted_len(&self.params(), 1 + secret.len());
+            (Mode::Encrypted, vec![0; len])
+        } else {
+            (Mode::Plaintext, secret.to_vec())
+        };
+
+        let data = self.enclose(mode, compression, body);
+        let fragmented = !self.codec.substitutes() && self.placement.fragments(usize::MAX) > 1;
+
+        Estimate::measure(
+            &data,
+            encrypted,
+            fragmented,
+            self.frames(),
+            self.codec,
+            &self.alphabet.unwrap_or_default(),
+        )
+    }
+
+    /// Hide a secret inside a message
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The secret you want to hide
+    /// * `password` - The password to encrypt the secret with, or `None` for plaintext
+    /// * `message` - The visible text everybody else will see
+    pub fn hide(
+        &self,
+        secret: impl AsRef<str>,
+        password: Option<&str>,
+        message: impl AsRef<str>,
+    ) -> Result<String, StegError> {
+        self.hide_bytes(secret.as_ref(), password, message)
+    }
+
+    /// Hide a binary secret inside a message
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The bytes you want to hide
+    /// * `password` - The password to encrypt the secret with, or `None` for plaintext
+    /// * `message` - The visible text everybody else will see
+    pub fn hide_bytes(
+        &self,
+        secret: impl AsRef<[u8]>,
+        password: Option<&str>,
+        message: impl AsRef<str>,
+    ) -> Result<String, StegError> {
+        self._hide(secret.as_ref(), password.into(), message.as_ref())
+    }
+
+    /// Hide a secret inside a message, with the secret and password wiped from memory when
+    /// they're dropped
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The secret you want to hide
+    /// * `password` - The password to encrypt the secret with, or `None` for plaintext
+    /// * `message` - The visible text everybody else will see
+    pub fn hide_secret(
+        &self,
+        secret: &SecretString,
+        password: Option<&SecretString>,
+        message: impl AsRef<str>,
+    ) -> Result<String, StegError> {
+        let password = password.map(|p| p.expose_secret());
+        self._hide(
+            secret.expose_secret().as_bytes(),
+            password.into(),
+            message.as_ref(),
+        )
+    }
+
+    /// Hide a secret inside a message, encrypted for X25519 public keys
+    ///
+    /// Any of the recipients' identities can reveal it with [`StegCloak::reveal_with_identity`]
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The secret you want to hide
+    /// * `recipients` - The public keys to encrypt the secret for
+    /// * `message` - The visible text everybody else will see
+    pub fn hide_for_recipients(
+        &self,
+        secret: impl AsRef<str>,
+        recipients: &[Recipient],
+        message: impl AsRef<str>,
+    ) -> Result<String, StegError> {
+        self.hide_bytes_for_recipients(secret.as_ref(), recipients, message)
+    }
+
+    /// Hide a binary secret inside a message, encrypted for X25519 public keys
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The bytes you want to hide
+    /// * `recipients` - The public keys to encrypt the secret for
+    /// * `message` - The visible text everybody else will see
+    pub fn hide_bytes_for_recipients(
+        &self,
+        secret: impl AsRef<[u8]>,
+        recipients: &[Recipient],
+        message: impl AsRef<str>,
+    ) -> Result<String, StegError> {
+        let protection = Protection::Recipients(recipients);
+        self._hide(secret.as_ref(), protection, message.as_ref())
+    }
+
+    /// Hide several secrets inside a message, each after a different space
+    ///
+    /// Every secret can be protected by its own password, and revealed independently of the
+    /// others with [`StegCloak::reveal`] or [`StegCloak::reveal_all`].
+    ///
+    /// # Arguments
+    ///
+    /// * `secrets` - The secrets you want to hide, with the password to encrypt each with
+    /// * `message` - The visible text everybody else will see, with a space for every secret
+    ///
+    /// Note: [`Placement::End`] only fits a single secret
+    pub fn hide_many(
+        &self,
+        secrets: &[(impl AsRef<[u8]>, Option<&str>)],
+        message: impl AsRef<str>,
+    ) -> Result<String, StegError> {
+        let message = message.as_ref();
+
+        if self.codec.substitutes() {
+            return match secrets {
+                [(secret, password)] => {
+                    let encoded = self.cloak(secret.as_ref(), (*password).into())?;
+                    self.within(codec::substitute(message, &encoded, self.codec)?)
+                }
+                _ => Err(CodecError::SingleStream.into()),
+            };
+        }
+
+        let streams = secrets
+            .iter()
+            .map(|(secret, password)| {
+                Ok((self.cloak(secret.as_ref(), (*password).into())?, *password))
+            })
+            .collect::<Result<Vec<_>, StegError>>()?;
+
+        self.within(self.insert(message, &streams)?)
+    }
+
+    pub(crate) fn _hide(
+        &self,
+        secret: &[u8],
+        protection: Protection,
+        message: &str,
+    ) -> Result<String, StegError> {
+        if self.codec.substitutes() {
+            let encoded = self.cloak(secret, protection)?;
+            return self.within(codec::substitute(message, &encoded, self.codec)?);
+        }
+
+        if self.placement.positions(message, 1).is_none() {
+            return Err(StegError::SpaceRequired);
+        }
+
+        let encoded = self.cloak(secret, protection)?;
+        self.within(self.insert(message, &[(encoded, protection.password())])?)
+    }
+
+    /// Check a message with hidden secrets against the limit, if there's one
+    fn within(&self, message: String) -> Result<String, StegError> {
+        let Some(limit) = self.limit else {
+            return Ok(message);
+        };
+
+        let length = limit.measure(&message);
+        if length > limit.max() {
+            return Err(StegError::LimitExceeded { length, limit });
+        }
+
+        Ok(message)
+    }
+
+    /// Insert encoded streams into `message` in order, along with the password each is keyed by
+    fn insert(
+        &self,
+        message: &str,
+        streams: &[(String, Option<&str>)],
+    ) -> Result<String, StegError> {
+        let Some(positions) = self.placement.positions(message, streams.len()) else {
+            return Err(StegError::SpaceRequired);
+        };
+
+        let alphabet = self.alphabet.unwrap_or_default();
+
+        let mut fragments = Vec::new();
+        for (positions, (encoded, password)) in positions.iter().zip(streams) {
+            let split = self.placement.fragments(positions.len());
+            let split = codec::fragment(encoded, split, &alphabet);
+
+            fragments.extend(
+                self.placement
+                    .pick(positions, split.len(), *password)
+                    .into_iter()
+                    .map(|pos| self.anchor(message, pos))
+                    .zip(split),
+            );
+        }
+
+        let mut message = message.to_owned();
+        // insert back to front, so earlier positions stay valid
+        for (pos, fragment) in fragments.into_iter().rev() {
+            message.insert_str(pos, &fragment);
+        }
+
+        Ok(message)
+    }
+
+    /// Variation selectors attach to the visible char before the space, instead of after it
+    fn anchor(&self, message: &str, pos: usize) -> usize {
+        match self.codec {
+            Codec::VariationSelectors if message[..pos].ends_with(' ') => pos - ' '.len_utf8(),
+            _ => pos,
+        }
+    }
+
+    /// Compress, protect and encode a secret into an invisible stream
+    fn cloak(&self, secret: &[u8], protection: Protection) -> Result<String, StegError> {
+        let data = self.seal(secret, protection)?;
+        let alphabet = self.alphabet.unwrap_or_default();
+
+        Ok(if self.frames() {
+            codec::encode_framed(&data, &alphabet)
+        } else {
+            codec::encode_as(&data, self.codec, &alphabet)
+        })
+    }
+
+    /// Whether the sealed data is encoded one error corrected shard at a time
+    fn frames(&self) -> bool {
+        self.redundancy > 0 && self.codec.frames()
+    }
+
+    /// Compressors tried on every secret, deflate first so it wins ties
+    fn compressors(&self) -> Vec<Compressor> {
+        let mut compressors = vec![
+            Compressor::Deflate {
+                level: self.compression_level,
+            },
+            Compressor::SmallText,
+            Compressor::Dictionary(Dictionary::preset()),
+        ];
+        compressors.extend(self.dictionary.clone().map(Compressor::Dictionary));
+        compressors.push(Compressor::Store);
+
+        compressors
+    }
+
+    /// Compress and protect a secret, into the bytes which get encoded
+    fn seal(&self, secret: &[u8], protection: Protection) -> Result<Vec<u8>, StegError> {
+        let (compression, secret) = compact::compress_smallest(secret, &self.compressors());
+
+        // encrypted secrets name their compression inside the payload, where it gives nothing away
+        let sealed = || {
+            let mut sealed = Zeroizing::new(Vec::with_capacity(1 + secret.len()));
+            sealed.push(compression.id());
+            sealed.extend_from_slice(&secret);
+            sealed
+        };
+
+        let (mode, body) = match protection {
+            Protection::Password(password) => {
+                let body = crypto::encrypt_with(password, &sealed(), &self.params())?;
+                (Mode::Encrypted, body)
+            }
+
+            Protection::Recipients(recipients) => {
+                (Mode::Recipients, recipient::encrypt(recipients, &sealed())?)
+            }
+
+            Protection::None => (Mode::Plaintext, secret.to_vec()),
+        };
+
+        Ok(self.enclose(mode, compression, body))
+    }
+
+    /// Encryption parameters of password protected secrets
+    fn params(&self) -> Params {
+        Params {
+            integrity: self.integrity,
+            kdf: self.kdf,
+            cipher: self.cipher,
+        }
+    }
+
+    /// Prepend the header and signature to a protected body, and add error correction
+    fn enclose(&self, mode: Mode, compression: Compression, body: Vec<u8>) -> Vec<u8> {
+        let integrity = match mode {
+            Mode::Encrypted if self.cipher.is_aead() => Integrity::Aead,
+            Mode::Encrypted if self.integrity => Integrity::Hmac,
+            Mode::Recipients => Integrity::Aead,
+            Mode::Encrypted | Mode::Plaintext => Integrity::None,
+        };
+
+        let compression = (mode == Mode::Plaintext).then_some(compression);
+        let header = Header::new(mode, compression, integrity)
+            .signed(self.signing_key.is_some())
+            .redundancy(self.redundancy)
+            .encode();
+
+        let mut protected = Vec::with_capacity(signature::SIZE + body.len());
+        if let Some(key) = &self.signing_key {
+            protected.extend(signature::sign(key, &header, &body));
+        }
+        protected.extend(body);
+
+        let mut data = header;
+        data.extend(protected);
+
+        // the header is corrected along with the rest
+        match self.redundancy {
+            0 => data,
+            redundancy => fec::encode(&data, redundancy),
+        }
+    }
+
+    /// Reveal a secret inside a message
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The visible text everybody else sees
+    /// * `password` - The password to decrypt the secret with, if it was encrypted
+    pub fn reveal(
+        &self,
+        message: impl AsRef<str>,
+        password: Option<&str>,
+    ) -> Result<String, StegError> {
+        to_string(self.reveal_bytes(message, password)?)
+    }
+
+    /// Reveal a binary secret inside a message
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The visible text everybody else sees
+    /// * `password` - The password to decrypt the secret with, if it was encrypted
+    pub fn reveal_bytes(
+        &self,
+        message: impl AsRef<str>,
+        password: Option<&str>,
+    ) -> Result<Vec<u8>, StegError> {
+        let key = password.map(Key::Password);
+        self._reveal(None, key, message.as_ref())
+    }
+
+    /// Reveal a secret inside a message, wiped from memory when it's dropped
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The visible text everybody else sees
+    /// * `password` - The password to decrypt the secret with, if it was encrypted
+    pub fn reveal_secret(
+        &self,
+        message: impl AsRef<str>,
+        password: Option<&SecretString>,
+    ) -> Result<SecretString, StegError> {
+        let password = password.map(|p| p.expose_secret());
+        let secret = Zeroizing::new(self.reveal(message, password)?);
+
+        // an exact size copy, since shrinking the string could leave a copy behind
+        Ok(SecretString::from(secret.as_str()))
+    }
+
+    /// Reveal a secret which was hidden for the identity's public key
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The visible text everybody else sees
+    /// * `identity` - The secret key matching one of the recipients
+    pub fn reveal_with_identity(
+        &self,
+        message: impl AsRef<str>,
+        identity: &Identity,
+    ) -> Result<String, StegError> {
+        to_string(self.reveal_bytes_with_identity(message, identity)?)
+    }
+
+    /// Reveal a binary secret which was hidden for the identity's public key
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The visible text everybody else sees
+    /// * `identity` - The secret key matching one of the recipients
+    pub fn reveal_bytes_with_identity(
+        &self,
+        message: impl AsRef<str>,
+        identity: &Identity,
+    ) -> Result<Vec<u8>, StegError> {
+        self._reveal(None, Some(Key::Identity(identity)), message.as_ref())
+    }
+
+    /// Hide a secret read from `secret` inside a message, writing the result to `output`
+    ///
+    /// The secret is compressed, encrypted and encoded as it's read, so memory use stays bounded
+    /// however large it is. Streaming supports AES-256-CTR only, without signing.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - Reader for the secret you want to hide
+    /// * `password` - The password to encrypt the secret with, or `None` for plaintext
+    /// * `message` - The visible text everybody else will see
+    /// * `output` - Where the message with the hidden secret is written
+    pub fn hide_stream(
+        &self,
+        mut secret: impl Read,
+        password: Option<&str>,
+        message: impl AsRef<str>,
+        mut output: impl Write,
+    ) -> Result<(), StegError> {
+        let message = message.as_ref();
+
+        if self.cipher.is_aead()
+            || self.signing_key.is_some()
+            || self.redundancy > 0
+            || self.limit.is_some()
+            || self.codec.substitutes()
+            || self.placement.fragments(usize::MAX) > 1
+        {
+            return Err(StegError::StreamingUnsupported);
+        }
+
+        let Some(positions) = self.placement.positions(message, 1) else {
+            return Err(StegError::SpaceRequired);
+        };
+
+        let pos = self.anchor(message, positions[0][0]);
+
+        output
+            .write_all(&message.as_bytes()[..pos])
+            .map_err(StegError::IoError)?;
+
+        let mut encoder =
+            codec::Encoder::with_codec(&mut output, self.codec, self.alphabet.unwrap_or_default());
+
+        let (mode, integrity) = match password {
+            Some(_) if self.integrity => (Mode::Encrypted, Integrity::Hmac),
+            Some(_) => (Mode::Encrypted, Integrity::None),
+            None => (Mode::Plaintext, Integrity::None),
+        };
+
+        let compression = password.is_none().then_some(Compression::Deflate);
+        let header = Header::new(mode, compression, integrity).encode();
+        encoder.write_all(&header).map_err(StegError::IoError)?;
+
+        match password {
+            Some(password) => {
+                let mut encrypter =
+                    EncryptWriter::new(password, self.integrity, self.kdf, encoder)?;
+                encrypter
+                    .write_all(&[Compression::Deflate.id()])
+                    .map_err(StegError::IoError)?;
+
+                let mut compressor = compact::compress_writer(encrypter, self.compression_level);
+                io::copy(&mut secret, &mut compressor).map_err(StegError::IoError)?;
+
+                let encrypter = compressor.finish().map_err(StegError::IoError)?;
+                let encoder = encrypter.finish().map_err(StegError::IoError)?;
+                encoder.finish().map_err(StegError::IoError)?;
+            }
+
+            None => {
+                let mut compressor = compact::compress_writer(encoder, self.compression_level);
+                io::copy(&mut secret, &mut compressor).map_err(StegError::IoError)?;
+
+                let encoder = compressor.finish().map_err(StegError::IoError)?;
+                encoder.finish().map_err(StegError::IoError)?;
+            }
+        }
+
+        output
+            .write_all(&message.as_bytes()[pos..])
+            .map_err(StegError::IoError)
+    }
+
+    /// Reveal a secret inside a message read from `message`, writing it to `output`
+    ///
+    /// Messages hidden with [`StegCloak::hide_stream`] are decoded, decrypted and decompressed
+    /// as they're read. Anything else is revealed from memory, like [`StegCloak::reveal_bytes`].
+    ///
+    /// Note: With integrity, tampering is only detected at the end, after the rest of the
+    ///       secret was already written to `output`
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - Reader for the visible text everybody else sees
+    /// * `password` - The password to decrypt the secret with, if it was encrypted
+    /// * `output` - Where the secret is written
+    pub fn reveal_stream(
+        &self,
+        message: impl Read,
+        password: Option<&str>,
+        mut output: impl Write,
+    ) -> Result<(), StegError> {
+        let message = BufReader::new(message);
+        let mut decoder = match self.alphabet {
+            Some(alphabet) => codec::Decoder::new(message, alphabet),
+            None => codec::Decoder::detect(message),
+        };
+
+        // the header and the first payload byte decide whether this can be streamed
+        let mut prefix = [0u8; header::SIZE + 1];
+        let len = read_prefix(&mut decoder, &mut prefix).map_err(io_error)?;
+        let prefix = &prefix[..len];
+
+        // headerless streams from 0.1.6 and before are small, they're revealed in one go, and
+        // error corrected ones have to be before their header can be read
+        let header = match Header::decode(prefix) {
+            _ if prefix.first() == Some(&header::FRAMED) => None,
+            Ok((header, _)) => Some(header),
+            Err(HeaderError::Missing) => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let streamable = match header {
+            Some(header)
+                if header
+                    .compression
+                    .is_some_and(|c| c != Compression::Deflate)
+                    || header.signed =>
+            {
+                false
+            }
+            Some(header) => match (header.mode, password) {
+                (Mode::Plaintext, _) => true,
+                (Mode::Encrypted, Some(_)) => {
+                    prefix.get(header::SIZE) == Some(&crypto::stream::PAYLOAD_VERSION)
+                }
+                _ => false,
+            },
+            None => false,
+        };
+
+        if !streamable {
+            let mut decoded = Vec::new();
+            let max = self.max_size.saturating_add(1);
+            prefix
+                .chain(decoder)
+                .take(max as u64)
+                .read_to_end(&mut decoded)
+                .map_err(io_error)?;
+
+            if decoded.len() > self.max_size {
+                return Err(CodecError::TooLarge.into());
+            }
+
+            let key = password.map(Key::Password);
+            let revealed = self.reveal_decoded(None, key, &decoded)?;
+
+            return output
+                .write_all(&revealed.secret)
+                .map_err(StegError::IoError);
+        }
+
+        let rest = (&prefix[header::SIZE..]).chain(decoder);
+        let result = match (password, header) {
+            (Some(password), Some(header)) if header.mode == Mode::Encrypted => {
+                let integrity = match header.integrity {
+                    Integrity::None => false,
+                    Integrity::Hmac => true,
+                    Integrity::Aead => return Err(DeEncryptError::IntegrityError.into()),
+                };
+
+                let mut decrypter = DecryptReader::with_integrity(password, integrity, rest)?;
+
+                if header.compression.is_none() {
+                    let mut id = [0];
+                    decrypter.read_exact(&mut id).map_err(io_error)?;
+                    if Compression::from_id(id[0]) != Some(Compression::Deflate) {
+                        return Err(DeCompressError::Malformed.into());
+                    }
+                }
+
+                io::copy(&mut compact::decompress_reader(decrypter), &mut output)
+            }
+
+            _ => io::copy(&mut compact::decompress_reader(rest), &mut output),
+        };
+
+        result.map(|_| ()).map_err(io_error)
+    }
+
+    /// Reveal every secret inside a message which the password opens
+    ///
+    /// Plaintext secrets open regardless of the password
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The visible text everybody else sees
+    /// * `password` - The password to decrypt the secrets with
+    pub fn reveal_all(
+        &self,
+        message: impl AsRef<str>,
+        password: Option<&str>,
+    ) -> Result<Vec<String>, StegError> {
+        self.reveal_all_bytes(message, password)?
+            .into_iter()
+            .map(to_string)
+            .collect()
+    }
+
+    /// Reveal every binary secret inside a message which the password opens
+    ///
+    /// Plaintext secrets open regardless of the password
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The visible text everybody else sees
+    /// * `password` - The password to decrypt the secrets with
+    pub fn reveal_all_bytes(
+        &self,
+        message: impl AsRef<str>,
+        password: Option<&str>,
+    ) -> Result<Vec<Vec<u8>>, StegError> {
+        let key = password.map(Key::Password);
+        let secrets = self
+            .reveal_streams(None, key, message.as_ref())
+            .filter_map(Result::ok)
+            .map(|revealed| revealed.secret)
+            .collect::<Vec<_>>();
+
+        if secrets.is_empty() {
+            // report why nothing could be revealed
+            self._reveal(None, key, message.as_ref())?;
+        }
+
+        Ok(secrets)
+    }
+
+    /// Reveal a signed secret inside a message
+    ///
+    /// Fails with [`StegError::NotSigned`] if the message wasn't signed
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The visible text everybody else sees
+    /// * `password` - The password to decrypt the secret with, if it was encrypted
+    pub fn reveal_signed(
+        &self,
+        message: impl AsRef<str>,
+        password: Option<&str>,
+    ) -> Result<Signed<String>, StegError> {
+        let Signed { secret, signer } = self.reveal_bytes_signed(message, password)?;
+
+        Ok(Signed {
+            secret: to_string(secret)?,
+            signer,
+        })
+    }
+
+    /// Reveal a signed binary secret inside a message
+    ///
+    /// Fails with [`StegError::NotSigned`] if the message wasn't signed
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The visible text everybody else sees
+    /// * `password` - The password to decrypt the secret with, if it was encrypted
+    pub fn reveal_bytes_signed(
+        &self,
+        message: impl AsRef<str>,
+        password: Option<&str>,
+    ) -> Result<Signed<Vec<u8>>, StegError> {
+        let key = password.map(Key::Password);
+        self._reveal_signed(None, key, message.as_ref())
+    }
+
+    /// Reveal a secret inside a message, along with the number of corrupted shards repaired
+    ///
+    /// Messages hidden without [`StegCloak::redundancy`] never have any corrections
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The visible text everybody else sees
+    /// * `password` - The password to decrypt the secret with, if it was encrypted
+    pub fn reveal_corrected(
+        &self,
+        message: impl AsRef<str>,
+        password: Option<&str>,
+    ) -> Result<Corrected<String>, StegError> {
+        let Corrected {
+            secret,
+            corrections,
+        } = self.reveal_bytes_corrected(message, password)?;
+
+        Ok(Corrected {
+            secret: to_string(secret)?,
+            corrections,
+        })
+    }
+
+    /// Reveal a binary secret inside a message, along with the number of corrupted shards
+    /// repaired
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The visible text everybody else sees
+    /// * `password` - The password to decrypt the secret with, if it was encrypted
+    pub fn reveal_bytes_corrected(
+        &self,
+        message: impl AsRef<str>,
+        password: Option<&str>,
+    ) -> Result<Corrected<Vec<u8>>, StegError> {
+        let key = password.map(Key::Password);
+        let revealed = self._reveal_any(None, key, message.as_ref())?;
+
+        Ok(Corrected {
+            secret: revealed.secret,
+            corrections: revealed.corrections,
+        })
+    }
+
+    /// Reveal the secret in `message`, dispatching on its header
+    ///
+    /// `expected` is the mode the caller requires, or `None` to accept whatever the header says.
+    /// Signed messages are always verified, even though the signer is discarded here.
+    pub(crate) fn _reveal(
+        &self,
+        expected: Option<Mode>,
+        key: Option<Key>,
+        message: &str,
+    ) -> Result<Vec<u8>, StegError> {
+        self._reveal_any(expected, key, message)
+            .map(|revealed| revealed.secret)
+    }
+
+    pub(crate) fn _reveal_signed(
+        &self,
+        expected: Option<Mode>,
+        key: Option<Key>,
+        message: &str,
+    ) -> Result<Signed<Vec<u8>>, StegError> {
+        match self._reveal_any(expected, key, message)? {
+            Revealed {
+                secret,
+                signer: Some(signer),
+                ..
+            } => Ok(Signed { secret, signer }),
+            _ => Err(StegError::NotSigned),
+        }
+    }
+
+    /// Reveal the first stream in `message` which the key opens
+    ///
+    /// If none of them open, the error of the first stream which at least had a valid header is
+    /// returned, since that's the one most likely meant for the caller
+    fn _reveal_any(
+        &self,
+        expected: Option<Mode>,
+        key: Option<Key>,
+        message: &str,
+    ) -> Result<Revealed, StegError> {
+        let mut error = None;
+        for result in self.reveal_streams(expected, key, message) {
+            match result {
+                Ok(revealed) => return Ok(revealed),
+
+                Err(e @ (StegError::CodecError(_) | StegError::HeaderError(_))) => {
+                    error.get_or_insert((false, e));
+                }
+
+                Err(e) => match error {
+                    Some((true, _)) => (),
+                    _ => error = Some((true, e)),
+                },
+            }
+        }
+
+        match error {
+            Some((_, e)) => Err(e),
+            None => Err(CodecError::ZwcDataNotfound.into()),
+        }
+    }
+
+    /// Reveal every stream in `message`, in the order they appear
+    fn reveal_streams<'a>(
+        &'a self,
+        expected: Option<Mode>,
+        key: Option<Key<'a>>,
+        message: &str,
+    ) -> impl Iterator<Item = Result<Revealed, StegError>> + 'a {
+        let found = match &self.alphabet {
+            Some(alphabet) => codec::find_all_with(message, alphabet),
+            None => codec::find_all(message)
+                .into_iter()
+                .map(|(_, stream)| stream)
+                .collect(),
+        };
+
+        found.into_iter().map(move |(_, decoded)| match decoded? {
+            decoded if decoded.len() > self.max_size => Err(CodecError::TooLarge.into()),
+            decoded => self.reveal_decoded(expected, key, &decoded),
+        })
+    }
+
+    fn reveal_decoded(
+        &self,
+        expected: Option<Mode>,
+        key: Option<Key>,
+        decoded: &[u8],
+    ) -> Result<Revealed, StegError> {
+        let (decoded, corrections) = fec::unframe(decoded)?;
+        let decoded = &decoded[..];
+
+        let (header, body) = match Header::decode(decoded) {
+            Err(HeaderError::Missing) => return self.reveal_unversioned(expected, key, decoded),
+            result => result?,
+        };
+
+        let (signer, body) = if header.signed {
+            let (signer, body) = signature::verify(&decoded[..header.size()], body)?;
+            (Some(signer), body)
+        } else {
+            (None, body)
+        };
+
+        check_mode(expected, header.mode)?;
+
+        let data = match (header.mode, key) {
+            (Mode::Plaintext, _) => Zeroizing::new(body.to_vec()),
+            (Mode::Encrypted, Some(Key::Password(password))) => {
+                check_integrity(header.integrity, body)?;
+                crypto::decrypt(password, body)?
+            }
+            (Mode::Encrypted, _) => return Err(StegError::PasswordRequired),
+            (Mode::Recipients, Some(Key::Identity(identity))) => {
+                recipient::decrypt(identity, body)?
+            }
+            (Mode::Recipients, _) => return Err(StegError::IdentityRequired),
+        };
+
+        let (compression, data) = match (header.compression, &data[..]) {
+            (Some(compression), data) => (compression, data),
+            (None, [id, data @ ..]) => {
+                let compression = Compression::from_id(*id).ok_or(DeCompressError::Malformed)?;
+                (compression, data)
+            }
+            (None, []) => return Err(DeCompressError::Malformed.into()),
+        };
+
+        let mut secret =
+            compact::decompress_as(data, compression, self.dictionary.as_ref(), self.max_size)?;
+
+        Ok(Revealed {
+            // handed over without a copy, it's up to the caller from here
+            secret: mem::take(&mut *secret),
+            signer,
+            corrections,
+        })
+    }
+
+    /// Reveal a headerless stream the way 0.1.6 and before wrote them
+    ///
+    /// Encrypted payloads start with their integrity flag, deflated text practically never does.
+    /// Plaintext has nothing else to check it by, so stray chars which don't inflate stay a
+    /// missing header rather than outranking the errors of real streams.
+    fn reveal_unversioned(
+        &self,
+        expected: Option<Mode>,
+        key: Option<Key>,
+        decoded: &[u8],
+    ) -> Result<Revealed, StegError> {
+        let decompress =
+            |data: &[u8]| compact::decompress_as(data, Compression::Deflate, None, self.max_size);
+
+        let mut secret = match (decoded.first(), key) {
+            (Some(0 | 1), key) => {
+                check_mode(expected, Mode::Encrypted)?;
+                match key {
+                    Some(Key::Password(password)) => {
+                        decompress(&crypto::decrypt(password, decoded)?)?
+                    }
+                    _ => return Err(StegError::PasswordRequired),
+                }
+            }
+
+            _ => {
+                let secret = decompress(decoded).map_err(|_| HeaderError::Missing)?;
+                check_mode(expected, Mode::Plaintext)?;
+                secret
+            }
+        };
+
+        Ok(Revealed {
+            secret: mem::take(&mut *secret),
+            signer: None,
+            corrections: 0,
+        })
+    }
+}
+
+/// Fail unless the payload is protected the way the header says, so an hmac can't be stripped
+/// or a cipher swapped for a weaker one
+fn check_integrity(integrity: Integrity, payload: &[u8]) -> Result<(), StegError> {
+    let actual = match crypto::suite(payload)? {
+        (cipher, _) if cipher.is_aead() => Integrity::Aead,
+        (_, true) => Integrity::Hmac,
+        (_, false) => Integrity::None,
+    };
+
+    if actual != integrity {
+        return Err(DeEncryptError::IntegrityError.into());
+    }
+
+    Ok(())
+}
+
+/// Fail with what the secret needs if it isn't protected the `expected` way
+fn check_mode(expected: Option<Mode>, mode: Mode) -> Result<(), StegError> {
+    if expected.is_some_and(|expected| expected != mode) {
+        return Err(match mode {
+            Mode::Plaintext => StegError::NotEncrypted,
+            Mode::Encrypted => StegError::PasswordRequired,
+            Mode::Recipients => StegError::IdentityRequired,
+        });
+    }
+
+    Ok(())
+}
+
+/// Read as much of `buf` as the stream has
+fn read_prefix(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+
+    Ok(len)
+}
+
+/// Recover the errors the streaming readers wrap in io errors
+fn io_error(e: io::Error) -> StegError {
+    if e.kind() != io::ErrorKind::InvalidData {
+        return StegError::IoError(e);
+    }
+
+    let e = match e.into_inner() {
+        Some(inner) => match inner.downcast::<CodecError>() {
+            Ok(e) => return (*e).into(),
+            Err(inner) => match inner.downcast::<DeEncryptError>() {
+                Ok(e) => return (*e).into(),
+                Err(inner) => io::Error::new(io::ErrorKind::InvalidData, inner),
+            },
+        },
+        None => io::Error::from(io::ErrorKind::InvalidData),
+    };
+
+    // corrupt deflate data
+    DeCompressError::IoError(e).into()
+}
+
+pub(crate) fn to_string(data: Vec<u8>) -> Result<String, StegError> {
+    Ok(compact::to_string(data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::Profile;
+
+    #[test]
+    fn test_last_space() {
+        let steg = StegCloak::new().placement(Placement::LastSpace);
+        let hidden = steg.hide("mysecret", None, "some cover text").unwrap();
+
+        assert!(hidden.starts_with("some cover "));
+        assert_eq!(steg.reveal(hidden, None).unwrap(), "mysecret");
+    }
+
+    #[test]
+    fn test_compression() {
+        let steg = StegCloak::new().kdf(Kdf::Pbkdf2 { rounds: 1_000 });
+
+        let cases: [(&[u8], Compression); 4] = [
+            (b"hi", Compression::SmallText),
+            (b"meet me at the station", Compression::Dictionary),
+            (&[0xff, 0xfe], Compression::Store),
+            (&[b'a'; 200], Compression::Deflate),
+        ];
+
+        for (secret, compression) in cases {
+            let data = steg.seal(secret, Protection::None).unwrap();
+            assert_eq!(
+                Header::decode(&data).unwrap().0.compression,
+                Some(compression)
+            );
+
+            let hidden = steg.hide_bytes(secret, None, "cover text").unwrap();
+            assert_eq!(steg.reveal_bytes(hidden, None).unwrap(), secret);
+
+            // encrypted, the choice is only revealed along with the secret
+            let data = steg.seal(secret, Protection::Password("123")).unwrap();
+            assert_eq!(Header::decode(&data).unwrap().0.compression, None);
+
+            let hidden = steg.hide_bytes(secret, Some("123"), "cover text").unwrap();
+            assert_eq!(steg.reveal_bytes(hidden, Some("123")).unwrap(), secret);
+        }
+    }
+
+    #[test]
+    fn test_integrity_mismatch() {
+        let steg = StegCloak::new()
+            .integrity(true)
+            .kdf(Kdf::Pbkdf2 { rounds: 1_000 });
+
+        let mut data = steg
+            .seal(b"attack at dawn", Protection::Password("123"))
+            .unwrap();
+        let (header, _) = Header::decode(&data).unwrap();
+        assert_eq!(header.integrity, Integrity::Hmac);
+
+        // the hmac follows the version, kdf, cipher, salt and key check of the payload
+        let hmac = header.size() + 30;
+        assert_eq!(data[hmac], 1);
+        data.splice(hmac..hmac + 33, [0]);
+        *data.last_mut().unwrap() ^= 1;
+
+        let hidden = format!("cover {}text", codec::encode(&data));
+        assert!(matches!(
+            steg.reveal(&hidden, Some("123")),
+            Err(StegError::DeEncryptError(DeEncryptError::IntegrityError))
+        ));
+    }
+
+    #[test]
+    fn test_secret_string() {
+        let steg = StegCloak::new();
+        let password = SecretString::from("mypassword");
+
+        let hidden = steg
+            .hide_secret(
+                &SecretString::from("mysecret"),
+                Some(&password),
+                "cover text",
+            )
+            .unwrap();
+        assert_eq!(
+            steg.reveal(&hidden, Some("mypassword")).unwrap(),
+            "mysecret"
+        );
+
+        let secret = steg.reveal_secret(&hidden, Some(&password)).unwrap();
+        assert_eq!(secret.expose_secret(), "mysecret");
+
+        assert!(matches!(
+            steg.reveal_secret(&hidden, Some(&SecretString::from("wrong"))),
+            Err(StegError::DeEncryptError(DeEncryptError::IncorrectPassword))
+        ));
+    }
+
+    #[test]
+    fn test_max_size() {
+        let secret = vec![0; 10_000];
+        let hidden = StegCloak::new()
+            .hide_bytes(&secret, None, "cover text")
+            .unwrap();
+
+        let steg = StegCloak::new().max_size(10_000);
+        assert_eq!(steg.reveal_bytes(&hidden, None).unwrap(), secret);
+
+        let steg = StegCloak::new().max_size(9_999);
+        assert!(matches!(
+            steg.reveal_bytes(&hidden, None),
+            Err(StegError::DeCompressError(DeCompressError::TooLarge(9_999)))
+        ));
+
+        // the decoded stream itself is over the limit
+        let steg = StegCloak::new().max_size(10);
+        assert!(matches!(
+            steg.reveal_bytes(&hidden, None),
+            Err(StegError::CodecError(CodecError::TooLarge))
+        ));
+
+        let hidden = StegCloak::new()
+            .redundancy(10)
+            .hide_bytes(&secret, None, "cover text")
+            .unwrap();
+        assert!(matches!(
+            steg.reveal_stream(hidden.as_bytes(), None, Vec::new()),
+            Err(StegError::CodecError(CodecError::TooLarge))
+        ));
+    }
+
+    #[test]
+    fn test_dictionary() {
+        let dictionary = Dictionary::new(1, ["correct horse battery staple"]).unwrap();
+        let steg = StegCloak::new().dictionary(dictionary);
+
+        let hidden = steg
+            .hide("correct horse battery staple", None, "cover text")
+            .unwrap();
+        assert_eq!(
+            steg.reveal(&hidden, None).unwrap(),
+            "correct horse battery staple"
+        );
+
+        assert!(matches!(
+            StegCloak::new().reveal(&hidden, None),
+            Err(StegError::DeCompressError(
+                DeCompressError::UnknownDictionary(1)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_hide_within() {
+        let cover = "some cover text";
+        let length = StegCloak::new()
+            .hide("mysecret", None, cover)
+            .unwrap()
+            .encode_utf16()
+            .count();
+
+        let steg = StegCloak::new().hide_within(Limit::Utf16(length));
+        assert!(steg.hide("mysecret", None, cover).is_ok());
+
+        let limit = Limit::Utf16(length - 1);
+        let steg = StegCloak::new().hide_within(limit);
+        assert!(matches!(
+            steg.hide("mysecret", None, cover),
+            Err(StegError::LimitExceeded { length: l, limit: e }) if l == length && e == limit
+        ));
+        assert!(matches!(
+            steg.hide_stream(&b"mysecret"[..], None, cover, Vec::new()),
+            Err(StegError::StreamingUnsupported)
+        ));
+    }
+
+    #[test]
+    fn test_redundancy() {
+        let secret = "a secret long enough to take a few shards ".repeat(4);
+
+        for codec in [Codec::Base4, Codec::Radix] {
+            let steg = StegCloak::new()
+                .codec(codec)
+                .redundancy(50)
+                .compression_level(0);
+
+            let hidden = steg.hide(&secret, None, "cover text").unwrap();
+            assert_eq!(
+                steg.reveal_corrected(&hidden, None).unwrap(),
+                Corrected {
+                    secret: secret.clone(),
+                    corrections: 0
+                }
+            );
+
+            let alphabet = Alphabet::default();
+            let chars = hidden.chars().collect::<Vec<_>>();
+            let start = chars.iter().position(|&c| alphabet.contains(c)).unwrap();
+            let mid = start
+                + chars[start..]
+                    .iter()
+                    .filter(|&&c| alphabet.contains(c))
+                    .count()
+                    / 2;
+
+            // a char changed right after the prefix, in the frame holding the header
+            let mut changed = chars.clone();
+            changed[start + 2] = alphabet.chars()[..4]
+                .iter()
+                .copied()
+                .find(|&c| c != chars[start + 2])
+                .unwrap();
+
+            let mut dropped = chars.clone();
+            dropped.remove(mid);
+
+            let mut doubled = chars.clone();
+            doubled.insert(mid, chars[mid]);
+
+            for corrupted in [changed, dropped, doubled].map(String::from_iter) {
+                let revealed = steg.reveal_corrected(&corrupted, None).unwrap();
+                assert_eq!(revealed.secret, secret);
+                assert!(revealed.corrections > 0);
+
+                let mut revealed = Vec::new();
+                steg.reveal_stream(corrupted.as_bytes(), None, &mut revealed)
+                    .unwrap();
+                assert_eq!(revealed, secret.as_bytes());
+            }
+
+            assert!(matches!(
+                steg.hide_stream(secret.as_bytes(), None, "cover text", Vec::new()),
+                Err(StegError::StreamingUnsupported)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_end() {
+        let steg = StegCloak::new().placement(Placement::End);
+        let hidden = steg.hide("mysecret", None, "cover").unwrap();
+
+        assert!(hidden.starts_with("cover"));
+        assert_eq!(steg.reveal(&hidden, None).unwrap(), "mysecret");
+
+        let mut revealed = Vec::new();
+        steg.reveal_stream(hidden.as_bytes(), None, &mut revealed)
+            .unwrap();
+        assert_eq!(revealed, b"mysecret");
+    }
+
+    #[test]
+    fn test_spread() {
+        let cover = "a cover text with quite a few words to spread the secret across";
+        let words = cover.split(' ').collect::<Vec<_>>();
+
+        for codec in [Codec::Base4, Codec::Radix, Codec::TagCharacters] {
+            for profile in [Profile::MaxCompatibility, Profile::JoinersOnly] {
+                let steg = StegCloak::new()
+                    .placement(Placement::Spread)
+                    .codec(codec)
+                    .alphabet(profile);
+                let hidden = steg.hide("mysecret", Some("123"), cover).unwrap();
+
+                // every word but the first starts with a fragment
+                let fragments = hidden.split(' ').collect::<Vec<_>>();
+                assert_eq!(fragments.len(), words.len());
+                for (fragment, word) in fragments[1..].iter().zip(&words[1..]) {
+                    assert!(fragment.ends_with(word) && fragment.len() > word.len());
+                }
+
+                assert_eq!(crate::strip(&hidden), cover);
+                assert_eq!(
+                    StegCloak::new().reveal(&hidden, Some("123")).unwrap(),
+                    "mysecret"
+                );
+
+                let mut revealed = Vec::new();
+                StegCloak::new()
+                    .reveal_stream(hidden.as_bytes(), Some("123"), &mut revealed)
+                    .unwrap();
+                assert_eq!(revealed, b"mysecret");
+            }
+        }
+
+        let steg = StegCloak::new().placement(Placement::Spread);
+        let secrets = [("first", None), ("second", None)];
+        let hidden = steg.hide_many(&secrets, cover).unwrap();
+        assert_eq!(steg.reveal_all(&hidden, None).unwrap(), ["first", "second"]);
+
+        assert!(matches!(
+            steg.hide_stream("mysecret".as_bytes(), None, cover, Vec::new()),
+            Err(StegError::StreamingUnsupported)
+        ));
+    }
+
+    #[test]
+    fn test_scattered() {
+        let cover = "a cover text with quite a few words to scatter the secret across";
+        let steg = StegCloak::new()
+            .placement(Placement::Scattered)
+            .kdf(Kdf::Pbkdf2 { rounds: 1_000 });
+
+        let gaps = |hidden: &str| {
+            hidden
+                .split(' ')
+                .map(|word| cover.split(' ').any(|w| w == word))
+                .collect::<Vec<_>>()
+        };
+
+        // the password keys the spaces, which are random without one
+        let hide = |password| steg.hide("mysecret", password, cover).unwrap();
+        let first = hide(Some("123"));
+        assert_eq!(gaps(&first), gaps(&hide(Some("123"))));
+        assert_ne!(gaps(&first), gaps(&hide(Some("456"))));
+
+        let plain = (0..4).map(|_| hide(None)).collect::<Vec<_>>();
+        assert!(plain.iter().any(|h| gaps(h) != gaps(&plain[0])));
+
+        assert_eq!(crate::strip(&first), cover);
+        assert_eq!(steg.reveal(&first, Some("123")).unwrap(), "mysecret");
+        for hidden in &plain {
+            assert_eq!(crate::strip(hidden), cover);
+            assert_eq!(steg.reveal(hidden, None).unwrap(), "mysecret");
+        }
+    }
+
+    #[test]
+    fn test_custom_settings() {
+        let alphabet = Alphabet::new([
+            '\u{200b}', '\u{2060}', '\u{feff}', '\u{200e}', '\u{200f}', '\u{180e}',
+        ])
+        .unwrap();
+
+        let steg = StegCloak::new()
+            .integrity(true)
+            .kdf(Kdf::Pbkdf2 { rounds: 1_000 })
+            .compression_level(1)
+            .alphabet(alphabet);
+
+        let hidden = steg.hide("mysecret", Some("123"), "cover text").unwrap();
+
+        assert_eq!(steg.reveal(&hidden, Some("123")).unwrap(), "mysecret");
+        assert!(StegCloak::new().reveal(&hidden, Some("123")).is_err());
+    }
+
+    #[test]
+    fn test_profile() {
+        let steg = StegCloak::new().alphabet(Profile::TagCharacters);
+        let hidden = steg.hide("mysecret", Some("123"), "cover text").unwrap();
+
+        assert!(!hidden.contains(codec::ZWC));
+        assert_eq!(
+            StegCloak::new().reveal(&hidden, Some("123")).unwrap(),
+            "mysecret"
+        );
+
+        let mut revealed = Vec::new();
+        StegCloak::new()
+            .reveal_stream(hidden.as_bytes(), Some("123"), &mut revealed)
+            .unwrap();
+        assert_eq!(revealed, b"mysecret");
+
+        // an explicit alphabet only reveals its own streams
+        assert!(StegCloak::new()
+            .alphabet(Profile::JoinersOnly)
+            .reveal(&hidden, Some("123"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_variation_selectors() {
+        let steg = StegCloak::new().codec(Codec::VariationSelectors);
+        let hidden = steg.hide("mysecret", Some("123"), "cover text").unwrap();
+
+        // attached to the "r" of "cover"
+        assert!(hidden.starts_with("cover\u{e01ef}"));
+        assert!(hidden.ends_with(" text"));
+        assert!(hidden.chars().count() < "cover text".len() + 64);
+
+        assert_eq!(
+            StegCloak::new().reveal(&hidden, Some("123")).unwrap(),
+            "mysecret"
+        );
+
+        let mut output = Vec::new();
+        steg.hide_stream(&b"mysecret"[..], None, "cover text", &mut output)
+            .unwrap();
+
+        let mut revealed = Vec::new();
+        StegCloak::new()
+            .reveal_stream(&output[..], None, &mut revealed)
+            .unwrap();
+        assert_eq!(revealed, b"mysecret");
+    }
+
+    #[test]
+    fn test_tag_characters() {
+        let steg = StegCloak::new().codec(Codec::TagCharacters);
+        let hidden = steg
+            .hide("mysecret".repeat(10), None, "cover text")
+            .unwrap();
+
+        assert!(hidden.starts_with("cover \u{e0001}"));
+        assert_eq!(crate::reveal(&hidden, None).unwrap(), "mysecret".repeat(10));
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let message = "word ".repeat(200);
+
+        let steg = StegCloak::new().codec(Codec::Whitespace);
+        let hidden = steg.hide("mysecret", Some("123"), &message).unwrap();
+
+        assert_eq!(hidden.chars().count(), message.chars().count());
+        assert_eq!(crate::strip(&hidden), message);
+        assert_eq!(
+            StegCloak::new().reveal(&hidden, Some("123")).unwrap(),
+            "mysecret"
+        );
+
+        let mut revealed = Vec::new();
+        StegCloak::new()
+            .reveal_stream(hidden.as_bytes(), Some("123"), &mut revealed)
+            .unwrap();
+        assert_eq!(revealed, b"mysecret");
+
+        assert!(matches!(
+            steg.hide("mysecret", Some("123"), "cover text"),
+            Err(StegError::CodecError(CodecError::NotEnoughSpaces))
+        ));
+    }
+
+    #[test]
+    fn test_homoglyphs() {
+        let message = "The quick brown fox jumps over the lazy dog. ".repeat(30);
+
+        let steg = StegCloak::new().codec(Codec::Homoglyphs);
+        let hidden = steg.hide("mysecret", None, &message).unwrap();
+
+        assert_ne!(hidden, message);
+        assert_eq!(hidden.chars().count(), message.chars().count());
+        assert_eq!(crate::strip(&hidden), message);
+        assert_eq!(StegCloak::new().reveal(&hidden, None).unwrap(), "mysecret");
+
+        assert!(matches!(
+            steg.hide("mysecret", None, "cover text"),
+            Err(StegError::CodecError(CodecError::NotEnoughLetters))
+        ));
+    }
+
+    #[test]
+    fn test_radix_codec() {
+        let steg = StegCloak::new().codec(Codec::Radix);
+
+        let hidden = steg.hide("mysecret", Some("123"), "cover text").unwrap();
+        let shorter = StegCloak::new()
+            .hide("mysecret", Some("123"), "cover text")
+            .unwrap();
+
+        assert!(hidden.chars().count() < shorter.chars().count());
+        assert_eq!(
+            StegCloak::new().reveal(&hidden, Some("123")).unwrap(),
+            "mysecret"
+        );
+
+        let mut streamed = Vec::new();
+        steg.hide_stream(&b"mysecret"[..], None, "cover text", &mut streamed)
+            .unwrap();
+
+        let mut revealed = Vec::new();
+        steg.reveal_stream(&streamed[..], None, &mut revealed)
+            .unwrap();
+        assert_eq!(revealed, b"mysecret");
+    }
+
+    #[test]
+    fn test_aead_cipher() {
+        let steg = StegCloak::new().cipher(Cipher::ChaCha20Poly1305);
+        let hidden = steg.hide("mysecret", Some("123"), "cover text").unwrap();
+
+        // the cipher is recorded in the payload, so any instance can reveal it
+        assert_eq!(
+            StegCloak::new().reveal(hidden, Some("123")).unwrap(),
+            "mysecret"
+        );
+    }
+
+    #[test]
+    fn test_recipients() {
+        let alice = Identity::generate();
+        let bob = Identity::generate();
+
+        let steg = StegCloak::new();
+        let hidden = steg
+            .hide_for_recipients(
+                "mysecret",
+                &[alice.to_public(), bob.to_public()],
+                "cover text",
+            )
+            .unwrap();
+
+        assert_eq!(
+            steg.reveal_with_identity(&hidden, &alice).unwrap(),
+            "mysecret"
+        );
+        assert_eq!(
+            steg.reveal_with_identity(&hidden, &bob).unwrap(),
+            "mysecret"
+        );
+        assert!(matches!(
+            steg.reveal(&hidden, Some("123")),
+            Err(StegError::IdentityRequired)
+        ));
+
+        let hidden = steg.hide("mysecret", Some("123"), "cover text").unwrap();
+        assert!(matches!(
+            steg.reveal_with_identity(&hidden, &alice),
+            Err(StegError::PasswordRequired)
+        ));
+    }
+
+    #[test]
+    fn test_signed() {
+        let key = signature::generate();
+        let steg = StegCloak::new().signing_key(key.clone());
+
+        let hidden = steg.hide("mysecret", Some("123"), "cover text").unwrap();
+        let signed = StegCloak::new()
+            .reveal_signed(&hidden, Some("123"))
+            .unwrap();
+
+        assert_eq!(signed.secret, "mysecret");
+        assert_eq!(signed.signer, key.verifying_key());
+        assert_eq!(steg.reveal(&hidden, Some("123")).unwrap(), "mysecret");
+
+        let hidden = StegCloak::new()
+            .hide("mysecret", None, "cover text")
+            .unwrap();
+        assert!(matches!(
+            steg.reveal_signed(&hidden, None),
+            Err(StegError::NotSigned)
+        ));
+    }
+
+    #[test]
+    fn test_hide_many() {
+        let steg = StegCloak::new().kdf(Kdf::Pbkdf2 { rounds: 1_000 });
+        let secrets = [
+            ("for alice", Some("alice")),
+            ("for bob", Some("bob")),
+            ("for everyone", None),
+        ];
+
+        let hidden = steg.hide_many(&secrets, "some longer cover text").unwrap();
+
+        assert_eq!(steg.reveal(&hidden, Some("bob")).unwrap(), "for bob");
+        assert_eq!(
+            steg.reveal_all(&hidden, Some("alice")).unwrap(),
+            ["for alice", "for everyone"]
+        );
+        assert!(matches!(
+            steg.reveal_all(&hidden, None),
+            Ok(secrets) if secrets == ["for everyone"]
+        ));
+
+        let hidden = steg.hide_many(&secrets[..2], "some cover text").unwrap();
+        assert!(matches!(
+            steg.reveal(&hidden, Some("eve")),
+            Err(StegError::DeEncryptError(_))
+        ));
+        assert!(matches!(
+            steg.hide_many(&secrets, "cover text"),
+            Err(StegError::SpaceRequired)
+        ));
+    }
+
+    #[test]
+    fn test_stream() {
+        let data = (0..200_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let steg = StegCloak::new()
+            .integrity(true)
+            .kdf(Kdf::Pbkdf2 { rounds: 1_000 });
+
+        for password in [None, Some("123")] {
+            let mut hidden = Vec::new();
+            steg.hide_stream(&data[..], password, "cover text", &mut hidden)
+                .unwrap();
+
+            let mut revealed = Vec::new();
+            steg.reveal_stream(&hidden[..], password, &mut revealed)
+                .unwrap();
+            assert_eq!(revealed, data);
+
+            // streamed messages reveal normally too
+            let hidden = String::from_utf8(hidden).unwrap();
+            assert_eq!(steg.reveal_bytes(&hidden, password).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_stream_integrity_stripped() {
+        let steg = StegCloak::new()
+            .integrity(true)
+            .kdf(Kdf::Pbkdf2 { rounds: 1_000 });
+
+        let mut hidden = Vec::new();
+        steg.hide_stream(
+            &b"attack at dawn"[..],
+            Some("123"),
+            "cover text",
+            &mut hidden,
+        )
+        .unwrap();
+
+        let mut data = codec::decode(std::str::from_utf8(&hidden).unwrap()).unwrap();
+        let (header, _) = Header::decode(&data).unwrap();
+        assert_eq!(header.integrity, Integrity::Hmac);
+
+        // the integrity flag follows the version, kdf, salt and key check of the payload
+        let flag = header.size() + 29;
+        assert_eq!(data[flag], 1);
+        data[flag] = 0;
+        data.truncate(data.len() - 32);
+        *data.last_mut().unwrap() ^= 1;
+
+        let hidden = format!("cover {}text", codec::encode(&data));
+        assert!(matches!(
+            steg.reveal_stream(hidden.as_bytes(), Some("123"), Vec::new()),
+            Err(StegError::DeEncryptError(DeEncryptError::IntegrityError))
+        ));
+    }
+
+    #[test]
+    fn test_stream_fallback() {
+        let hidden = StegCloak::new()
+            .cipher(Cipher::ChaCha20Poly1305)
+            .hide("mysecret", Some("123"), "cover text")
+            .unwrap();
+
+        let mut revealed = Vec::new();
+        StegCloak::new()
+            .reveal_stream(hidden.as_bytes(), Some("123"), &mut revealed)
+            .unwrap();
+        assert_eq!(revealed, b"mysecret");
+
+        assert!(matches!(
+            StegCloak::new().reveal_stream(hidden.as_bytes(), None, &mut Vec::new()),
+            Err(StegError::PasswordRequired)
+        ));
+        assert!(matches!(
+            StegCloak::new().reveal_stream(&b"cover text"[..], None, &mut Vec::new()),
+            Err(StegError::CodecError(CodecError::ZwcDataNotfound))
+        ));
+        assert!(matches!(
+            StegCloak::new()
+                .cipher(Cipher::ChaCha20Poly1305)
+                .hide_stream(&b"mysecret"[..], None, "cover text", &mut Vec::new()),
+            Err(StegError::StreamingUnsupported)
+        ));
+    }
+
+    #[test]
+    fn test_space_required() {
+        assert!(matches!(
+            StegCloak::new().hide("mysecret", None, "cover"),
+            Err(StegError::SpaceRequired)
+        ));
+    }
+}
@@ -1,23 +1,390 @@
+mod framed;
+mod homoglyph;
+mod radix;
+mod slots;
+mod stream;
+mod tag;
+mod variation;
+mod whitespace;
+
+use std::ops::Range;
+
 use aho_corasick::AhoCorasick;
 use itertools::Itertools as _;
 
-const ZWC: &[char] = &[
+use crate::{compact, header};
+use slots::Slots;
+pub use stream::{Decoder, Encoder};
+
+/// The invisible characters of the default alphabet
+pub const ZWC: &[char] = &[
     '\u{200c}', '\u{200d}', '\u{2061}', '\u{2062}', '\u{2063}', '\u{2064}',
 ];
 
 const _: () = assert!(
-    ZWC.len() % 2 == 0,
+    ZWC.len().is_multiple_of(2),
     "ZWC must contain an even number of characters"
 );
 
 const BASE: usize = 4;
 
-fn is_zwc(c: char) -> bool {
-    ZWC.contains(&c)
+/// Index of the alphabet char after the marker which starts a fragmented stream
+const FRAGMENTED: usize = 2;
+
+/// Index of the alphabet char after the marker which starts an error corrected stream
+const FRAMED: usize = 3;
+
+/// Alphabet digits holding the fragment count, after the fragmented prefix
+const COUNT_DIGITS: u32 = 4;
+
+/// Most fragments a stream can be split into by [`fragment`]
+pub const MAX_FRAGMENTS: usize = (BASE + 2).pow(COUNT_DIGITS);
+
+/// The 6 invisible characters a stream is encoded with
+///
+/// The first 4 carry 2 bits each, the last 2 are used to shorten the most common doubled pairs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Alphabet([char; BASE + 2]);
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Self([ZWC[0], ZWC[1], ZWC[2], ZWC[3], ZWC[4], ZWC[5]])
+    }
+}
+
+impl Alphabet {
+    /// Create a custom alphabet
+    ///
+    /// Requirements:
+    /// - all chars are unique
+    /// - no char is a space, since spaces are used to position the stream
+    pub fn new(chars: [char; BASE + 2]) -> Result<Self, CodecError> {
+        if chars.contains(&' ') || !chars.iter().all_unique() {
+            return Err(CodecError::InvalidAlphabet);
+        }
+
+        Ok(Self(chars))
+    }
+
+    /// The characters of this alphabet
+    pub fn chars(&self) -> &[char] {
+        &self.0
+    }
+
+    /// The built-in profile this alphabet belongs to, if any
+    pub fn profile(&self) -> Option<Profile> {
+        Profile::ALL.into_iter().find(|p| p.alphabet() == *self)
+    }
+
+    pub(crate) fn contains(&self, c: char) -> bool {
+        self.0.contains(&c)
+    }
+}
+
+impl From<Profile> for Alphabet {
+    fn from(profile: Profile) -> Self {
+        profile.alphabet()
+    }
+}
+
+/// Built-in alphabets, for platforms which strip different invisible characters
+///
+/// Streams of every profile but [`Profile::MaxCompatibility`] start with their own marker char,
+/// so [`decode`] can tell which profile a stream was encoded with.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Profile {
+    /// The default [`ZWC`] alphabet, joiners and invisible operators
+    #[default]
+    MaxCompatibility,
+    /// Joiners and other zero width format chars, for platforms which strip invisible operators
+    JoinersOnly,
+    /// Variation selectors 1-6, which most renderers hide
+    VariationSelectors,
+    /// Tag characters from the U+E0000 block
+    TagCharacters,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 4] = [
+        Profile::MaxCompatibility,
+        Profile::JoinersOnly,
+        Profile::VariationSelectors,
+        Profile::TagCharacters,
+    ];
+
+    /// The alphabet of this profile
+    ///
+    /// Each marker char is only part of its own profile.
+    pub fn alphabet(&self) -> Alphabet {
+        match self {
+            Profile::MaxCompatibility => Alphabet::default(),
+            Profile::JoinersOnly => Alphabet([
+                '\u{200b}', '\u{2060}', '\u{034f}', '\u{200c}', '\u{feff}', '\u{200d}',
+            ]),
+            Profile::VariationSelectors => Alphabet([
+                '\u{fe00}', '\u{fe01}', '\u{fe02}', '\u{fe03}', '\u{fe04}', '\u{fe05}',
+            ]),
+            Profile::TagCharacters => Alphabet([
+                '\u{e0020}',
+                '\u{e0021}',
+                '\u{e0022}',
+                '\u{e0023}',
+                '\u{e0024}',
+                '\u{e0025}',
+            ]),
+        }
+    }
+}
+
+/// How data is mapped onto the characters of an alphabet
+///
+/// Streams start with the first dyn char followed by the char of the codec id, which a base4
+/// stream never starts with. So decoding picks the right codec on its own. Only base4 streams
+/// with the default alphabet leave this out, to stay compatible with older messages.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Codec {
+    /// 2 bits per char, with the 2 most common doubled chars shortened
+    #[default]
+    Base4,
+    /// Radix conversion of 8 byte blocks, using every char of the alphabet as a digit
+    ///
+    /// About 3.1 chars per byte with the 6 char alphabet, compared to about 3.7 for base4
+    Radix,
+    /// One variation selector per byte, ignoring the alphabet
+    ///
+    /// Selectors attach to the visible char in front of them, so the stream is inserted before
+    /// a space instead of after it. Most selectors take 4 bytes in UTF-8 and 2 units in UTF-16.
+    VariationSelectors,
+    /// 7 bits per char from the tag block (U+E0000 - U+E007F), ignoring the alphabet
+    ///
+    /// About 1.14 chars per byte, every one taking 4 bytes in UTF-8 and 2 units in UTF-16
+    TagCharacters,
+    /// 3 bits in the choice of each space, from spaces about as wide as a regular one
+    ///
+    /// Survives sanitizers which strip invisible chars, but needs a space for every 3 bits plus
+    /// 14 for its prefix. The encoded spaces replace those of the cover text, see
+    /// [`substitute`], and only one stream fits in a text.
+    Whitespace,
+    /// 1 bit in the choice of each letter with a lookalike, Latin or Cyrillic / Greek
+    ///
+    /// Like [`Codec::Whitespace`], it substitutes letters of the cover text instead of adding
+    /// chars, so nothing is stripped, but it needs 8 such letters per byte plus 48 for its
+    /// prefix. See [`capacity`] to check a cover text.
+    Homoglyphs,
+}
+
+impl Codec {
+    /// Index of the alphabet char which identifies the codec, after the marker
+    ///
+    /// `None` for codecs with a marker of their own
+    fn id(&self) -> Option<usize> {
+        match self {
+            Codec::Radix => Some(0),
+            Codec::Base4 => Some(1),
+            Codec::VariationSelectors
+            | Codec::TagCharacters
+            | Codec::Whitespace
+            | Codec::Homoglyphs => None,
+        }
+    }
+
+    /// The char a stream of this codec starts with, for codecs that don't use the alphabet
+    fn marker(&self) -> Option<char> {
+        match self {
+            Codec::VariationSelectors => Some(variation::MARKER),
+            Codec::TagCharacters => Some(tag::MARKER),
+            Codec::Base4 | Codec::Radix | Codec::Whitespace | Codec::Homoglyphs => None,
+        }
+    }
+
+    /// Whether error corrected data can be written with [`encode_framed`] instead
+    pub fn frames(&self) -> bool {
+        matches!(self, Codec::Base4 | Codec::Radix)
+    }
+
+    /// Whether the stream substitutes chars of the cover text, instead of being inserted
+    pub fn substitutes(&self) -> bool {
+        self.slots().is_some()
+    }
+
+    fn slots(&self) -> Option<&'static Slots> {
+        match self {
+            Codec::Whitespace => Some(&whitespace::SLOTS),
+            Codec::Homoglyphs => Some(&homoglyph::SLOTS),
+            Codec::Base4 | Codec::Radix | Codec::VariationSelectors | Codec::TagCharacters => None,
+        }
+    }
+
+    /// The error for a cover text with too few chars to substitute
+    fn not_enough(&self) -> CodecError {
+        match self {
+            Codec::Homoglyphs => CodecError::NotEnoughLetters,
+            _ => CodecError::NotEnoughSpaces,
+        }
+    }
+
+    /// The widest char in UTF-8 a stream of this codec can contain, so also in UTF-16
+    pub(crate) fn widest(&self, alphabet: &Alphabet) -> char {
+        let chars = match self.slots() {
+            Some(slots) => (0..slots.radix)
+                .map(|d| (slots.with_digit)(slots.zero, d))
+                .collect(),
+            None => match self.marker() {
+                // supplementary plane chars, as wide as any
+                Some(marker) => vec![marker],
+                None => alphabet.0.to_vec(),
+            },
+        };
+
+        chars.into_iter().max_by_key(|c| c.len_utf8()).unwrap()
+    }
+
+    fn from_id(id: usize) -> Option<Self> {
+        match id {
+            0 => Some(Codec::Radix),
+            1 => Some(Codec::Base4),
+            _ => None,
+        }
+    }
+
+    /// The marker and id chars a stream of this codec starts with
+    fn prefix(&self, alphabet: &Alphabet) -> String {
+        match (self.marker(), self.id()) {
+            (Some(marker), _) => marker.to_string(),
+            _ if *self == Codec::Base4 && *alphabet == Alphabet::default() => String::new(),
+            (None, Some(id)) => String::from_iter([alphabet.0[BASE], alphabet.0[id]]),
+            // substituted streams start with a prefix block of their own
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Encode binary data with a specific codec
+pub fn encode_as(data: &[u8], codec: Codec, alphabet: &Alphabet) -> String {
+    let mut result = codec.prefix(alphabet);
+
+    match codec {
+        Codec::Base4 => result.push_str(&encode_with(data, alphabet)),
+        Codec::Radix => {
+            for block in data.chunks(radix::BLOCK) {
+                radix::encode_block(block, &alphabet.0, &mut result);
+            }
+        }
+        Codec::VariationSelectors => result.extend(data.iter().map(|&b| variation::to_char(b))),
+        Codec::TagCharacters => {
+            for block in data.chunks(tag::BLOCK) {
+                tag::encode_block(block, &mut result);
+            }
+        }
+        Codec::Whitespace | Codec::Homoglyphs => {
+            let slots = codec.slots().unwrap();
+            result.extend(
+                slots
+                    .encode(data)
+                    .into_iter()
+                    .map(|d| (slots.with_digit)(slots.zero, d)),
+            );
+        }
+    }
+
+    result
+}
+
+/// Encode error corrected data, as returned by [`crate::fec::encode`], one frame at a time
+///
+/// Each frame is written in radix 5 and followed by a separator char, so decoding gets back in
+/// step after a dropped or added char and only loses the frame it was in.
+///
+/// # Panics
+///
+/// If the data doesn't start with [`header::FRAMED`].
+pub fn encode_framed(data: &[u8], alphabet: &Alphabet) -> String {
+    let zwc = &alphabet.0;
+    let frames = data
+        .strip_prefix(&[header::FRAMED])
+        .expect("data isn't error corrected");
+
+    let mut result = String::from_iter([zwc[BASE], zwc[FRAMED]]);
+    for (i, frame) in frames.chunks(crate::fec::FRAME).enumerate() {
+        if i > 0 {
+            result.push(zwc[framed::SEPARATOR]);
+        }
+        result.extend(framed::encode(frame).map(|d| zwc[d]));
+    }
+
+    result
+}
+
+/// Most chars [`encode_as`] produces for `len` bytes of data, whatever they are
+///
+/// Only base4 streams vary, when their most common pairs get shortened.
+pub(crate) fn max_chars(len: usize, codec: Codec, alphabet: &Alphabet) -> usize {
+    match codec {
+        Codec::Base4 => codec.prefix(alphabet).chars().count() + 2 + len * BASE,
+        _ => encode_as(&vec![0; len], codec, alphabet).chars().count(),
+    }
+}
+
+/// Substitute chars of `message` with those a [`Codec::substitutes`] codec encoded, in order
+///
+/// # Panics
+///
+/// If the codec inserts its stream instead.
+pub fn substitute(message: &str, encoded: &str, codec: Codec) -> Result<String, CodecError> {
+    let slots = codec.slots().expect("codec doesn't substitute");
+    let digits = encoded.chars().filter_map(slots.digit).collect::<Vec<_>>();
+
+    slots
+        .substitute(message, &digits)
+        .ok_or_else(|| codec.not_enough())
+}
+
+/// Number of data bytes a [`Codec::substitutes`] codec can hide in `message`
+///
+/// `None` for codecs which insert their stream, which fits in any text.
+pub fn capacity(message: &str, codec: Codec) -> Option<usize> {
+    let slots = codec.slots()?;
+    Some(slots.capacity(slots.count(message)))
+}
+
+/// Split an encoded stream into up to `n` fragments, to be inserted in order into a text
+///
+/// Every fragment gets at least one char, and the first starts with the fragment count, so
+/// decoding gathers them back from the visible text in between. With a single fragment, the
+/// stream is returned as is.
+pub fn fragment(encoded: &str, n: usize, alphabet: &Alphabet) -> Vec<String> {
+    let chars = encoded.chars().collect::<Vec<_>>();
+    let n = n.min(chars.len()).min(MAX_FRAGMENTS);
+
+    if n <= 1 {
+        return vec![encoded.to_owned()];
+    }
+
+    let mut fragments = (0..n)
+        .map(|i| String::from_iter(&chars[i * chars.len() / n..(i + 1) * chars.len() / n]))
+        .collect::<Vec<_>>();
+
+    let zwc = &alphabet.0;
+    let mut prefix = String::from_iter([zwc[BASE], zwc[FRAGMENTED]]);
+    let mut count = n - 1;
+    for _ in 0..COUNT_DIGITS {
+        prefix.push(zwc[count % zwc.len()]);
+        count /= zwc.len();
+    }
+
+    fragments[0].insert_str(0, &prefix);
+    fragments
 }
 
 /// Encode binary data to String using zwc characters
 pub fn encode(data: &[u8]) -> String {
+    encode_with(data, &Alphabet::default())
+}
+
+/// Encode binary data to String using the characters of a custom alphabet
+pub fn encode_with(data: &[u8], alphabet: &Alphabet) -> String {
+    let zwc = &alphabet.0;
+
     // 4 chars per byte
     let required_zwc = data.len() * BASE;
     let mut buffer = String::with_capacity(required_zwc);
@@ -34,19 +401,19 @@ pub fn encode(data: &[u8]) -> String {
             let zwc_char = match (bit2, bit1) {
                 (0, 0) => {
                     counts[0] += 1;
-                    ZWC[0]
+                    zwc[0]
                 }
                 (0, 1) => {
                     counts[1] += 1;
-                    ZWC[1]
+                    zwc[1]
                 }
                 (1, 0) => {
                     counts[2] += 1;
-                    ZWC[2]
+                    zwc[2]
                 }
                 (1, 1) => {
                     counts[3] += 1;
-                    ZWC[3]
+                    zwc[3]
                 }
                 _ => unreachable!(),
             };
@@ -57,12 +424,10 @@ pub fn encode(data: &[u8]) -> String {
 
     // now it's encoded, but we can compress it a little further
 
-    let table = &[
-        "\u{200c}\u{200c}",
-        "\u{200d}\u{200d}",
-        "\u{2061}\u{2061}",
-        "\u{2062}\u{2062}",
-    ];
+    let table = zwc[..BASE]
+        .iter()
+        .map(|c| format!("{c}{c}"))
+        .collect::<Vec<_>>();
 
     let mut tmp1 = [0u8; 4];
     let mut tmp2 = [0u8; 4];
@@ -72,32 +437,30 @@ pub fn encode(data: &[u8]) -> String {
 
     // first common
     if let Some(i) = counts.iter().position_max() {
-        chars.push(ZWC[i]);
+        chars.push(zwc[i]);
         // reset counter so we can get the next highest
         counts[i] = 0;
 
-        let zwc_char = ZWC[BASE].encode_utf8(&mut tmp1);
+        let zwc_char = zwc[BASE].encode_utf8(&mut tmp1);
 
-        find.push(table[i]);
+        find.push(&table[i]);
         replace.push(zwc_char);
     } else {
         // use BASE + 1 as a sentinel since it's dynamic and unreplaceable
-        chars.push(ZWC[BASE + 1]);
+        chars.push(zwc[BASE + 1]);
     }
 
     // second common
     if let Some(i) = counts.iter().position_max() {
-        chars.push(ZWC[i]);
-        // reset counter so we can get the next highest
-        counts[i] = 0;
+        chars.push(zwc[i]);
 
-        let zwc_char = ZWC[BASE + 1].encode_utf8(&mut tmp2);
+        let zwc_char = zwc[BASE + 1].encode_utf8(&mut tmp2);
 
-        find.push(table[i]);
+        find.push(&table[i]);
         replace.push(zwc_char);
     } else {
         // use BASE + 1 as a sentinel since it's dynamic and unreplaceable
-        chars.push(ZWC[BASE + 1]);
+        chars.push(zwc[BASE + 1]);
     }
 
     let ac = AhoCorasick::new(find).unwrap();
@@ -112,78 +475,435 @@ pub fn encode(data: &[u8]) -> String {
 
 /// Decode binary data that was previously encoded into a String using zwc characters
 ///
-/// Note: String must have minimum 1 space, and zwc stream must be right after the space
-///       For example " {zwc_stream_here}"
+/// The alphabet is detected from the built-in [`Profile`]s.
+///
+/// Note: The zwc stream may be anywhere in the string, decoding starts at the first zwc
+///       For example "cover {zwc_stream_here}text"
 pub fn decode(string: &str) -> Result<Vec<u8>, CodecError> {
-    let Some(mut pos) = string.find(' ') else {
-        return Err(CodecError::ZwcDataNotfound);
+    find_all(string)
+        .into_iter()
+        .next()
+        .map_or(Err(CodecError::ZwcDataNotfound), |(_, (_, result))| {
+            capped(result)
+        })
+}
+
+/// Decode binary data that was previously encoded with a custom alphabet
+pub fn decode_with(string: &str, alphabet: &Alphabet) -> Result<Vec<u8>, CodecError> {
+    capped(decode_first(string, alphabet))
+}
+
+fn decode_first(string: &str, alphabet: &Alphabet) -> Result<Vec<u8>, CodecError> {
+    let pos = string.find(|c| starts_stream(c, alphabet));
+
+    match (pos, find_substituted(string).into_iter().next()) {
+        (Some(pos), Some((_, (range, _)))) if pos < range.start => {
+            decode_stream(&string[pos..], alphabet).map(|(bytes, _)| bytes)
+        }
+        (_, Some((_, (_, result)))) => result,
+        (Some(pos), None) => decode_stream(&string[pos..], alphabet).map(|(bytes, _)| bytes),
+        (None, None) => Err(CodecError::ZwcDataNotfound),
+    }
+}
+
+/// Decode every zwc stream in the string, in the order they appear
+///
+/// Streams which fail to decode are returned as errors, so the others can still be used
+pub fn decode_all(string: &str) -> Vec<Result<Vec<u8>, CodecError>> {
+    find_all(string)
+        .into_iter()
+        .map(|(_, (_, result))| capped(result))
+        .collect()
+}
+
+/// Decode every stream in the string that was encoded with a custom alphabet
+pub fn decode_all_with(string: &str, alphabet: &Alphabet) -> Vec<Result<Vec<u8>, CodecError>> {
+    find_all_with(string, alphabet)
+        .into_iter()
+        .map(|(_, result)| capped(result))
+        .collect()
+}
+
+/// Refuse decoded data over the [`compact::MAX_SIZE`] cap
+///
+/// The decoded data is never larger than the text it came from, but that may be untrusted too.
+fn capped(result: Result<Vec<u8>, CodecError>) -> Result<Vec<u8>, CodecError> {
+    match result {
+        Ok(bytes) if bytes.len() > compact::MAX_SIZE => Err(CodecError::TooLarge),
+        result => result,
+    }
+}
+
+/// Byte range of a stream in a string, and its decoded data
+///
+/// Unlike the `decode` functions, finding streams doesn't cap their size.
+pub type FoundStream = (Range<usize>, Result<Vec<u8>, CodecError>);
+
+/// Decode every stream of any built-in profile in the string, in the order they appear
+pub fn find_all(string: &str) -> Vec<(Profile, FoundStream)> {
+    let mut streams = Vec::new();
+    for profile in Profile::ALL {
+        let alphabet = profile.alphabet();
+
+        for (range, result) in find_all_with(string, &alphabet) {
+            // other profiles always start with their marker, anything else is a stray char
+            if profile != Profile::MaxCompatibility
+                && !string[range.start..].starts_with(alphabet.0[BASE])
+            {
+                continue;
+            }
+
+            streams.push((profile, (range, result)));
+        }
+    }
+
+    // profiles share some chars, so a marked stream also shows up as default streams inside it
+    let marked = streams
+        .iter()
+        .filter(|(profile, _)| *profile != Profile::MaxCompatibility)
+        .map(|(_, (range, _))| range.clone())
+        .collect::<Vec<_>>();
+
+    streams.retain(|(profile, (range, _))| {
+        *profile != Profile::MaxCompatibility || !marked.iter().any(|m| m.contains(&range.start))
+    });
+
+    streams.sort_by_key(|(_, (range, _))| range.start);
+    streams
+}
+
+/// Decode every stream in the string, along with the byte range each stream occupies
+pub fn find_all_with(string: &str, alphabet: &Alphabet) -> Vec<FoundStream> {
+    let mut streams = Vec::new();
+
+    let mut offset = 0;
+    while let Some(pos) = string[offset..].find(|c| starts_stream(c, alphabet)) {
+        let start = offset + pos;
+
+        match decode_stream(&string[start..], alphabet) {
+            Ok((bytes, len)) => {
+                offset = start + len;
+                streams.push((start..offset, Ok(bytes)));
+            }
+
+            Err(e) => {
+                // skip the rest of the broken stream, at least the char it started with
+                let first = string[start..].chars().next().map_or(0, char::len_utf8);
+                offset = string[start + first..]
+                    .find(|c| !alphabet.contains(c))
+                    .map_or(string.len(), |len| start + first + len);
+                streams.push((start..offset, Err(e)));
+            }
+        }
+    }
+
+    for (_, stream) in find_substituted(string) {
+        let pos = streams.partition_point(|(range, _)| range.start < stream.0.start);
+        streams.insert(pos, stream);
+    }
+
+    streams
+}
+
+/// Codecs which substitute chars of the cover text
+const SUBSTITUTING: [Codec; 2] = [Codec::Whitespace, Codec::Homoglyphs];
+
+/// Decode the streams of [`Codec::substitutes`] codecs in the string, in the order they appear
+///
+/// There's at most one per codec, its range spanning from the first to the last substituted
+/// char.
+pub fn find_substituted(string: &str) -> Vec<(Codec, FoundStream)> {
+    let mut streams = SUBSTITUTING
+        .into_iter()
+        .filter_map(|codec| Some((codec, codec.slots()?.find(string)?)))
+        .collect::<Vec<_>>();
+
+    streams.sort_by_key(|(_, (range, _))| range.start);
+    streams
+}
+
+/// `c` changed back to digit 0 if it's a char `codec` substitutes, which is the char a cover
+/// text most likely had
+pub fn restore(c: char, codec: Codec) -> char {
+    codec.slots().map_or(c, |slots| slots.restore(c))
+}
+
+/// Whether `c` can be part of a stream, of any codec which inserts its stream into the text
+pub fn is_stream_char(c: char, alphabet: &Alphabet) -> bool {
+    alphabet.contains(c) || variation::from_char(c).is_some() || tag::from_char(c).is_some()
+}
+
+/// Rough number of bytes a stream which failed to decode carried, going by the codec its
+/// prefix names
+pub(crate) fn estimate_len(stream: &str, alphabet: &Alphabet) -> usize {
+    let zwc = &alphabet.0;
+    let count = |s: &str| s.chars().filter(|&c| is_stream_char(c, alphabet)).count();
+
+    let mut chars = stream.chars();
+    match (chars.next(), chars.next()) {
+        (Some(variation::MARKER), _) => count(stream) - 1,
+        (Some(tag::MARKER), _) => (count(stream) - 1) * tag::BLOCK / tag::BLOCK_CHARS,
+
+        (Some(marker), Some(id)) if marker == zwc[BASE] => {
+            let rest = &stream[marker.len_utf8() + id.len_utf8()..];
+
+            match zwc.iter().position(|&c| c == id) {
+                // the gathered fragments start with the prefix of their codec
+                Some(FRAGMENTED) => {
+                    let gathered = rest
+                        .chars()
+                        .filter(|&c| is_stream_char(c, alphabet))
+                        .skip(COUNT_DIGITS as usize)
+                        .collect::<String>();
+                    estimate_len(&gathered, alphabet)
+                }
+                Some(FRAMED) => count(rest) * crate::fec::FRAME / (framed::digits() + 1),
+                Some(id) if Codec::from_id(id) == Some(Codec::Radix) => {
+                    count(rest) * radix::BLOCK / radix::digits(zwc.len(), radix::BLOCK)
+                }
+                _ => count(rest).saturating_sub(2) / BASE,
+            }
+        }
+
+        // every byte takes 4 chars, ignoring the 2 leading dyn chars
+        (Some(c), _) if alphabet.contains(c) => count(stream).saturating_sub(2) / BASE,
+
+        _ => SUBSTITUTING
+            .into_iter()
+            .filter_map(|codec| codec.slots())
+            .find(|slots| stream.chars().next().and_then(slots.digit).is_some())
+            .map_or(0, |slots| slots.capacity(slots.count(stream))),
+    }
+}
+
+/// Whether a stream can start with `c`
+fn starts_stream(c: char, alphabet: &Alphabet) -> bool {
+    alphabet.contains(c) || c == variation::MARKER || c == tag::MARKER
+}
+
+/// Decode the stream at the start of `string`
+///
+/// Returns the data and the length of the stream in bytes
+fn decode_stream(string: &str, alphabet: &Alphabet) -> Result<(Vec<u8>, usize), CodecError> {
+    let zwc = &alphabet.0;
+
+    if string.starts_with(variation::MARKER) {
+        return variation::decode(string);
+    }
+
+    if string.starts_with(tag::MARKER) {
+        return tag::decode(string);
+    }
+
+    let mut chars = string.chars();
+    let (Some(marker), Some(id)) = (chars.next(), chars.next()) else {
+        return Err(CodecError::MalformedData);
     };
 
-    // skip space
-    pos += 1;
+    // base4 streams never start with a dyn char
+    if marker != zwc[BASE] {
+        return decode_base4(string, alphabet);
+    }
+
+    let prefix_len = marker.len_utf8() + id.len_utf8();
+    if id == zwc[FRAGMENTED] {
+        let (bytes, len) = decode_fragments(&string[prefix_len..], alphabet)?;
+        return Ok((bytes, prefix_len + len));
+    }
+
+    if id == zwc[FRAMED] {
+        let (bytes, len) = decode_framed(&string[prefix_len..], alphabet);
+        return Ok((bytes, prefix_len + len));
+    }
+
+    let codec = zwc
+        .iter()
+        .position(|&c| c == id)
+        .and_then(Codec::from_id)
+        .ok_or(CodecError::UnknownCodec)?;
 
-    // minimum pos + 2 positional chars needed (each char is 3 bytes long)
-    if string.len() - 1 <= pos + 6 {
+    let (bytes, len) = match codec {
+        Codec::Base4 => decode_base4(&string[prefix_len..], alphabet)?,
+        Codec::Radix => {
+            // minimum 1 digit needed
+            if chars.next().is_none() {
+                return Err(CodecError::MalformedData);
+            }
+
+            decode_radix(&string[prefix_len..], alphabet)?
+        }
+        Codec::VariationSelectors
+        | Codec::TagCharacters
+        | Codec::Whitespace
+        | Codec::Homoglyphs => unreachable!("has no id"),
+    };
+
+    Ok((bytes, prefix_len + len))
+}
+
+/// Gather the fragments of a stream, starting with the fragment count, and decode them
+fn decode_fragments(string: &str, alphabet: &Alphabet) -> Result<(Vec<u8>, usize), CodecError> {
+    let zwc = &alphabet.0;
+
+    let digits = string
+        .chars()
+        .take(COUNT_DIGITS as usize)
+        .map(|c| zwc.iter().position(|&z| z == c))
+        .collect::<Option<Vec<_>>>()
+        .filter(|digits| digits.len() == COUNT_DIGITS as usize)
+        .ok_or(CodecError::MalformedData)?;
+
+    let count = digits
+        .iter()
+        .rev()
+        .fold(0, |count, d| count * zwc.len() + d)
+        + 1;
+
+    let mut offset = string
+        .chars()
+        .take(COUNT_DIGITS as usize)
+        .map(char::len_utf8)
+        .sum::<usize>();
+
+    let mut gathered = String::new();
+    for i in 0..count {
+        // the text between fragments is skipped
+        if i > 0 {
+            offset += string[offset..]
+                .find(|c| is_stream_char(c, alphabet))
+                .ok_or(CodecError::MalformedData)?;
+        }
+
+        let len = string[offset..]
+            .find(|c| !is_stream_char(c, alphabet))
+            .unwrap_or(string.len() - offset);
+
+        gathered.push_str(&string[offset..offset + len]);
+        offset += len;
+    }
+
+    // fragments are never nested
+    if gathered.starts_with(&String::from_iter([zwc[BASE], zwc[FRAGMENTED]])) {
         return Err(CodecError::MalformedData);
     }
 
-    let mut string = string[pos..].to_owned();
+    let (bytes, _) = decode_stream(&gathered, alphabet)?;
+    Ok((bytes, offset))
+}
+
+/// Decode the frames of an error corrected stream, up to the end of the stream
+///
+/// Frames which are broken decode to zeros, for [`crate::fec`] to correct, so this never fails.
+fn decode_framed(string: &str, alphabet: &Alphabet) -> (Vec<u8>, usize) {
+    let zwc = &alphabet.0;
+
+    let end = string
+        .find(|c| !alphabet.contains(c))
+        .unwrap_or(string.len());
+
+    let digits = string[..end]
+        .chars()
+        .map(|c| zwc.iter().position(|&z| z == c).unwrap())
+        .collect::<Vec<_>>();
+
+    let mut bytes = vec![header::FRAMED];
+    for run in digits.split(|&d| d == framed::SEPARATOR) {
+        framed::decode(run, &mut bytes);
+    }
+
+    (bytes, end)
+}
+
+/// Decode the base4 stream at the start of `string`
+fn decode_base4(string: &str, alphabet: &Alphabet) -> Result<(Vec<u8>, usize), CodecError> {
+    let zwc = &alphabet.0;
 
     //
     // process first 2 dyn chars
     //
-    let mut chars = string.chars();
-    let Some((dyn1, dyn2)) = chars.next_tuple() else {
+    let mut chars = string.char_indices();
+    let (Some((_, dyn1)), Some((_, dyn2))) = (chars.next(), chars.next()) else {
         return Err(CodecError::MalformedData);
     };
 
-    let dyns = &ZWC[BASE..];
-
-    if !dyns.contains(&dyn1) {
-        string = string.replace(ZWC[BASE], &format!("{dyn1}{dyn1}"));
+    // minimum 2 positional chars + data needed
+    if chars.clone().next().is_none() {
+        return Err(CodecError::MalformedData);
     }
 
-    if !dyns.contains(&dyn2) {
-        string = string.replace(ZWC[BASE + 1], &format!("{dyn2}{dyn2}"));
-    }
+    let dyns = &zwc[BASE..];
 
-    // slice of rest of the string data, ignoring the first 2 pos chars
-    let chars = string.chars().skip(2).chunks(4);
+    // dyn chars stand for a doubled data char, unless they're unused sentinels
+    let expand = |c: char| match c {
+        _ if c == zwc[BASE] && !dyns.contains(&dyn1) => (dyn1, 2),
+        _ if c == zwc[BASE + 1] && !dyns.contains(&dyn2) => (dyn2, 2),
+        _ => (c, 1),
+    };
 
     let mut bytes = Vec::with_capacity(string.len().div_ceil(BASE));
-    for mut chunk in chars.into_iter() {
-        let Some((one, two, three, four)) = chunk.next_tuple() else {
-            break;
-        };
+    let mut end = string.len();
 
-        // detect end of stream, since all chars are not zwc anymore
-        if !is_zwc(one) && !is_zwc(two) && !is_zwc(three) && !is_zwc(four) {
-            break;
-        }
+    let mut byte = 0u8;
+    let mut filled = 0;
+    for (i, c) in chars {
+        if !alphabet.contains(c) {
+            // detect end of stream, since the next chunk doesn't start with zwc anymore
+            if filled == 0 {
+                end = i;
+                break;
+            }
 
-        // however, if any others are not zwc, our stream is broken
-        if !(is_zwc(one) && is_zwc(two) && is_zwc(three) && is_zwc(four)) {
+            // however, if a chunk is cut short, our stream is broken
             return Err(CodecError::MalformedData);
         }
 
-        let mut byte = 0u8;
-
-        for (b, c) in (0..=6).step_by(2).zip([one, two, three, four]) {
+        let (c, count) = expand(c);
+        for _ in 0..count {
             let bit_pattern = match c {
-                _ if c == ZWC[0] => 0b00,
-                _ if c == ZWC[1] => 0b01,
-                _ if c == ZWC[2] => 0b10,
-                _ if c == ZWC[3] => 0b11,
-                _ => unreachable!(),
+                _ if c == zwc[0] => 0b00,
+                _ if c == zwc[1] => 0b01,
+                _ if c == zwc[2] => 0b10,
+                _ if c == zwc[3] => 0b11,
+                // a dyn char which wasn't expanded
+                _ => return Err(CodecError::MalformedData),
             };
 
-            byte |= bit_pattern << b;
+            // LE
+            byte |= bit_pattern << (filled * 2);
+            filled += 1;
+
+            if filled == BASE {
+                bytes.push(byte);
+                byte = 0;
+                filled = 0;
+            }
         }
+    }
+
+    Ok((bytes, end))
+}
+
+/// Decode radix blocks up to the end of the stream
+fn decode_radix(string: &str, alphabet: &Alphabet) -> Result<(Vec<u8>, usize), CodecError> {
+    let zwc = &alphabet.0;
+    let radix = zwc.len();
+
+    let end = string
+        .find(|c| !alphabet.contains(c))
+        .unwrap_or(string.len());
+
+    let digits = string[..end]
+        .chars()
+        .map(|c| zwc.iter().position(|&z| z == c).unwrap())
+        .collect::<Vec<_>>();
 
-        bytes.push(byte);
+    let block_len = radix::digits(radix, radix::BLOCK);
+    let mut bytes = Vec::with_capacity(digits.len() / block_len * radix::BLOCK + radix::BLOCK);
+    for block in digits.chunks(block_len) {
+        radix::decode_block(block, radix, &mut bytes).ok_or(CodecError::MalformedData)?;
     }
 
-    Ok(bytes)
+    Ok((bytes, end))
 }
 
 #[derive(Debug, PartialEq, thiserror::Error)]
@@ -192,6 +912,18 @@ pub enum CodecError {
     MalformedData,
     #[error("Stream contains no zwc data")]
     ZwcDataNotfound,
+    #[error("Alphabet chars must be unique and not contain a space")]
+    InvalidAlphabet,
+    #[error("Stream uses an unknown codec")]
+    UnknownCodec,
+    #[error("Text does not contain enough spaces to carry the data")]
+    NotEnoughSpaces,
+    #[error("Text does not contain enough letters with lookalikes to carry the data")]
+    NotEnoughLetters,
+    #[error("Codec only fits a single stream in a text")]
+    SingleStream,
+    #[error("Decoded stream is larger than the size limit")]
+    TooLarge,
 }
 
 #[cfg(test)]
@@ -247,6 +979,266 @@ mod tests {
         assert_eq!(decode("cover \u{2062}\u{2062}aaabbbcccddd"), Ok(vec![]));
     }
 
+    #[test]
+    fn test_custom_alphabet() {
+        let alphabet = Alphabet::new([
+            '\u{200b}', '\u{2060}', '\u{feff}', '\u{200e}', '\u{200f}', '\u{180e}',
+        ])
+        .unwrap();
+        let data = (0..=255).collect::<Vec<_>>();
+
+        let encoded = encode_with(&data, &alphabet);
+        assert!(encoded.chars().all(|c| alphabet.chars().contains(&c)));
+
+        let decoded = decode_with(&format!("cover {encoded} text"), &alphabet).unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_invalid_alphabet() {
+        assert_eq!(
+            Alphabet::new(['a', 'a', 'c', 'd', 'e', 'f']),
+            Err(CodecError::InvalidAlphabet)
+        );
+        assert_eq!(
+            Alphabet::new(['a', ' ', 'c', 'd', 'e', 'f']),
+            Err(CodecError::InvalidAlphabet)
+        );
+    }
+
+    #[test]
+    fn test_decode_no_space() {
+        let data = (0..=255).collect::<Vec<_>>();
+        let encoded = encode(&data);
+
+        assert_eq!(decode(&format!("cover{encoded}")), Ok(data));
+    }
+
+    #[test]
+    fn test_radix() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        let encoded = encode_as(&data, Codec::Radix, &Alphabet::default());
+
+        assert!(encoded.chars().count() < encode(&data).chars().count());
+        assert_eq!(decode(&format!("cover {encoded} text")), Ok(data));
+
+        for len in 1..=17 {
+            let data = vec![0xff; len];
+            let encoded = encode_as(&data, Codec::Radix, &Alphabet::default());
+
+            assert_eq!(decode(&encoded), Ok(data));
+        }
+    }
+
+    #[test]
+    fn test_radix_malformed() {
+        // a short block with no matching byte count
+        let encoded = format!("{}{}{}", ZWC[4], ZWC[0], ZWC[1].to_string().repeat(5));
+        assert_eq!(decode(&encoded), Err(CodecError::MalformedData));
+
+        let encoded = format!("{}{}{}", ZWC[4], ZWC[5], ZWC[1].to_string().repeat(4));
+        assert_eq!(decode(&encoded), Err(CodecError::UnknownCodec));
+    }
+
+    #[test]
+    fn test_framed() {
+        let frames = (0..3 * crate::fec::FRAME as u8).collect::<Vec<u8>>();
+        let data = [&[header::FRAMED], &frames[..]].concat();
+        let encoded = encode_framed(&data, &Alphabet::default())
+            .chars()
+            .collect::<Vec<_>>();
+        let text = |chars: &[char]| format!("cover {} text", String::from_iter(chars));
+
+        assert_eq!(decode(&text(&encoded)), Ok(data.clone()));
+
+        // a dropped or doubled char only zeroes the frame it was in
+        let second = framed::digits() + 2 + framed::digits() / 2;
+        let mut broken = data.clone();
+        broken[1 + crate::fec::FRAME..1 + 2 * crate::fec::FRAME].fill(0);
+
+        let dropped = [&encoded[..second], &encoded[second + 1..]].concat();
+        assert_eq!(decode(&text(&dropped)), Ok(broken.clone()));
+
+        let doubled = [&encoded[..=second], &encoded[second..]].concat();
+        assert_eq!(decode(&text(&doubled)), Ok(broken));
+
+        // a dropped or doubled separator changes nothing
+        let separator = framed::digits() + 2;
+        assert_eq!(encoded[separator], ZWC[framed::SEPARATOR]);
+
+        let dropped = [&encoded[..separator], &encoded[separator + 1..]].concat();
+        assert_eq!(decode(&text(&dropped)), Ok(data.clone()));
+
+        let doubled = [&encoded[..=separator], &encoded[separator..]].concat();
+        assert_eq!(decode(&text(&doubled)), Ok(data));
+    }
+
+    #[test]
+    fn test_profiles() {
+        let data = (0..=255).collect::<Vec<u8>>();
+
+        for profile in Profile::ALL {
+            let alphabet = profile.alphabet();
+            assert_eq!(alphabet.profile(), Some(profile));
+
+            for codec in [Codec::Base4, Codec::Radix] {
+                let encoded = encode_as(&data, codec, &alphabet);
+                assert!(encoded.chars().all(|c| alphabet.contains(c)));
+
+                assert_eq!(decode(&format!("cover {encoded} text")), Ok(data.clone()));
+            }
+        }
+
+        // joiners share chars with the default alphabet
+        let joiners = encode_as(&[1, 2, 3], Codec::Base4, &Profile::JoinersOnly.alphabet());
+        let default = encode(&[4, 5, 6]);
+
+        let found = find_all(&format!("a {joiners} b {default} c"));
+        assert_eq!(
+            found
+                .into_iter()
+                .map(|(profile, (_, data))| (profile, data))
+                .collect::<Vec<_>>(),
+            vec![
+                (Profile::JoinersOnly, Ok(vec![1, 2, 3])),
+                (Profile::MaxCompatibility, Ok(vec![4, 5, 6])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_variation_selectors() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        let encoded = encode_as(&data, Codec::VariationSelectors, &Alphabet::default());
+
+        assert_eq!(encoded.chars().count(), data.len() + 1);
+        assert_eq!(decode(&format!("cover{encoded} text")), Ok(data.clone()));
+        assert_eq!(
+            decode_with(
+                &format!("\u{1f600}{encoded}"),
+                &Profile::JoinersOnly.alphabet()
+            ),
+            Ok(data)
+        );
+
+        // selectors of the variation selector profile are part of the data
+        let encoded = encode_as(&[4, 0, 5], Codec::VariationSelectors, &Alphabet::default());
+        assert_eq!(decode_all(&encoded), vec![Ok(vec![4, 0, 5])]);
+
+        assert_eq!(
+            decode_all(&format!("a{} b", variation::MARKER)),
+            vec![Err(CodecError::MalformedData)]
+        );
+    }
+
+    #[test]
+    fn test_tag_characters() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        let encoded = encode_as(&data, Codec::TagCharacters, &Alphabet::default());
+
+        assert_eq!(encoded.chars().count(), (data.len() * 8).div_ceil(7) + 1);
+        assert_eq!(decode(&format!("cover {encoded}text")), Ok(data));
+
+        for len in 1..=15 {
+            let data = vec![0x5a; len];
+            let encoded = encode_as(&data, Codec::TagCharacters, &Alphabet::default());
+
+            assert_eq!(decode_all(&encoded), vec![Ok(data)]);
+        }
+
+        // a flag emoji is a tag sequence, but not a stream
+        let flag = "\u{1f3f4}\u{e0067}\u{e0062}\u{e0065}\u{e006e}\u{e0067}\u{e007f}";
+        assert!(decode_all(flag).is_empty());
+
+        assert_eq!(
+            decode(&format!("a {}\u{e0020} b", tag::MARKER)),
+            Err(CodecError::MalformedData)
+        );
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let data = vec![1, 2, 3];
+        let spaces = encode_as(&data, Codec::Whitespace, &Alphabet::default());
+        let message = substitute(&"word ".repeat(30), &spaces, Codec::Whitespace).unwrap();
+
+        assert_eq!(decode(&message), Ok(data.clone()));
+
+        // along with a zwc stream, in the order they start
+        let both = format!("{}{message}", encode(&[4, 5, 6]));
+        assert_eq!(decode_all(&both), vec![Ok(vec![4, 5, 6]), Ok(data)]);
+
+        // regular spaces aren't a stream
+        assert!(decode_all(&"word\u{a0}".repeat(30)).is_empty());
+    }
+
+    #[test]
+    fn test_fragments() {
+        let data = (0..32).collect::<Vec<u8>>();
+        let encoded = encode(&data);
+
+        let fragments = fragment(&encoded, 5, &Alphabet::default());
+        assert_eq!(fragments.len(), 5);
+
+        let text = fragments
+            .iter()
+            .map(|f| format!("word {f}"))
+            .collect::<String>();
+        assert_eq!(decode_all(&format!("{text} end")), vec![Ok(data.clone())]);
+
+        // a missing fragment breaks the stream
+        let text = fragments[..4]
+            .iter()
+            .map(|f| format!("word {f}"))
+            .collect::<String>();
+        assert_eq!(decode(&text), Err(CodecError::MalformedData));
+
+        assert_eq!(fragment(&encoded, 1, &Alphabet::default()), [encoded]);
+    }
+
+    #[test]
+    fn test_homoglyphs() {
+        let cover = "Pack my box with five dozen liquor jugs. ".repeat(10);
+        assert_eq!(capacity(&cover, Codec::Homoglyphs), Some(19));
+        assert_eq!(capacity(&cover, Codec::Radix), None);
+
+        let data = b"lookalike".to_vec();
+        let letters = encode_as(&data, Codec::Homoglyphs, &Alphabet::default());
+        let message = substitute(&cover, &letters, Codec::Homoglyphs).unwrap();
+
+        assert_eq!(message.chars().count(), cover.chars().count());
+        assert_eq!(decode(&message), Ok(data));
+
+        let restored = message
+            .chars()
+            .map(|c| restore(c, Codec::Homoglyphs))
+            .collect::<String>();
+        assert_eq!(restored, cover);
+
+        assert_eq!(
+            substitute("too few", &letters, Codec::Homoglyphs),
+            Err(CodecError::NotEnoughLetters)
+        );
+    }
+
+    #[test]
+    fn test_decode_all() {
+        let first = encode(&[1, 2, 3]);
+        let second = encode(&[4, 5, 6]);
+
+        let decoded = decode_all(&format!("a {first}b c{second} \u{2062}"));
+        assert_eq!(
+            decoded,
+            vec![
+                Ok(vec![1, 2, 3]),
+                Ok(vec![4, 5, 6]),
+                Err(CodecError::MalformedData)
+            ]
+        );
+
+        assert!(decode_all("cover text").is_empty());
+    }
+
     #[test]
     fn test_midstream_malformed() {
         assert_eq!(
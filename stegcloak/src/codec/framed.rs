@@ -0,0 +1,92 @@
+//! Frames of error corrected streams, which get back in step after dropped or added chars
+//!
+//! Every frame of [`fec::FRAME`] bytes is written in radix 5 with the first 5 chars of the
+//! alphabet, and frames are separated by the last one. A frame with a char too few or too many
+//! only fails its own checksum, and a lost or doubled separator shows in the length of the run
+//! of digits between separators.
+
+use super::radix;
+use crate::fec;
+
+/// Radix of the digits, which leaves the last alphabet char to separate frames
+pub(super) const RADIX: usize = 5;
+
+/// Index of the alphabet char which separates frames
+pub(super) const SEPARATOR: usize = RADIX;
+
+/// Most frames a run of digits without separators decodes to, so a decoder never holds on to
+/// more than that many
+pub(super) const MAX_RUN: usize = 16;
+
+/// Number of digits a frame takes
+pub(super) fn digits() -> usize {
+    let full = fec::FRAME / radix::BLOCK * radix::digits(RADIX, radix::BLOCK);
+
+    match fec::FRAME % radix::BLOCK {
+        0 => full,
+        rest => full + radix::digits(RADIX, rest),
+    }
+}
+
+/// Digits of a frame, least significant first within each block
+pub(super) fn encode(frame: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    frame
+        .chunks(radix::BLOCK)
+        .flat_map(|block| radix::encode_digits(block, RADIX))
+}
+
+/// Decode a run of digits between separators, into as many frames as its length is closest to
+///
+/// Frames which don't decode are left as zeros, which their checksum doesn't match.
+pub(super) fn decode(run: &[usize], out: &mut Vec<u8>) {
+    let len = digits();
+
+    for run in run.chunks(len * MAX_RUN) {
+        let frames = (run.len() + len / 2) / len;
+        if run.len() != frames * len {
+            out.resize(out.len() + frames * fec::FRAME, 0);
+            continue;
+        }
+
+        for frame in run.chunks(len) {
+            let start = out.len();
+            let decoded = frame
+                .chunks(radix::digits(RADIX, radix::BLOCK))
+                .all(|block| radix::decode_block(block, RADIX, out).is_some());
+
+            if !decoded {
+                out.truncate(start);
+                out.resize(start + fec::FRAME, 0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs() {
+        let frame = (0..fec::FRAME as u8).collect::<Vec<_>>();
+        let digits = encode(&frame).collect::<Vec<_>>();
+        assert_eq!(digits.len(), super::digits());
+
+        let decoded = |run: &[usize]| {
+            let mut out = Vec::new();
+            decode(run, &mut out);
+            out
+        };
+
+        assert_eq!(decoded(&digits), frame);
+        assert_eq!(
+            decoded(&[digits.clone(), digits.clone()].concat()),
+            [&frame[..], &frame].concat()
+        );
+        assert_eq!(decoded(&digits[..3]), []);
+
+        // a digit too few or too many still takes up a frame, which doesn't check out
+        assert_eq!(decoded(&digits[1..]), [0; fec::FRAME]);
+        assert_eq!(decoded(&[&digits[..], &[0]].concat()), [0; fec::FRAME]);
+    }
+}
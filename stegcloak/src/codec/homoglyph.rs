@@ -0,0 +1,105 @@
+//! One bit per letter with a lookalike, choosing between the Latin letter and its Cyrillic or
+//! Greek confusable
+
+use super::slots::Slots;
+
+/// Latin letters and the lookalike standing for a 1 bit
+///
+/// Only pairs which render the same in common fonts, so lookalikes like Latin `r` and Cyrillic
+/// `г` are left out.
+const CONFUSABLES: [(char, char); 32] = [
+    ('a', '\u{430}'),
+    ('c', '\u{441}'),
+    ('d', '\u{501}'),
+    ('e', '\u{435}'),
+    ('h', '\u{4bb}'),
+    ('i', '\u{456}'),
+    ('j', '\u{458}'),
+    ('l', '\u{4cf}'),
+    ('o', '\u{43e}'),
+    ('p', '\u{440}'),
+    ('q', '\u{51b}'),
+    ('s', '\u{455}'),
+    ('w', '\u{51d}'),
+    ('x', '\u{445}'),
+    ('y', '\u{443}'),
+    ('A', '\u{410}'),
+    ('B', '\u{412}'),
+    ('C', '\u{421}'),
+    ('E', '\u{415}'),
+    ('H', '\u{41d}'),
+    ('I', '\u{406}'),
+    ('J', '\u{408}'),
+    ('K', '\u{41a}'),
+    ('M', '\u{41c}'),
+    ('N', '\u{39d}'),
+    ('O', '\u{41e}'),
+    ('P', '\u{420}'),
+    ('S', '\u{405}'),
+    ('T', '\u{422}'),
+    ('X', '\u{425}'),
+    ('Y', '\u{3a5}'),
+    ('Z', '\u{396}'),
+];
+
+pub(super) const SLOTS: Slots = Slots {
+    magic: &[0x9b, 0x4c],
+    radix: 2,
+    digit,
+    with_digit,
+    zero: 'a',
+};
+
+fn pair(c: char) -> Option<(char, char)> {
+    CONFUSABLES
+        .iter()
+        .copied()
+        .find(|&(latin, lookalike)| c == latin || c == lookalike)
+}
+
+/// 0 for a Latin letter, 1 for its lookalike
+fn digit(c: char) -> Option<usize> {
+    pair(c).map(|(latin, _)| usize::from(c != latin))
+}
+
+fn with_digit(c: char, digit: usize) -> char {
+    match pair(c) {
+        Some((latin, _)) if digit == 0 => latin,
+        Some((_, lookalike)) => lookalike,
+        None => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let message = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+
+        let data = b"homoglyphs".to_vec();
+        assert!(SLOTS.capacity(SLOTS.count(&message)) >= data.len());
+
+        let hidden = SLOTS.substitute(&message, &SLOTS.encode(&data)).unwrap();
+        assert_ne!(hidden, message);
+        assert_eq!(hidden.chars().count(), message.chars().count());
+
+        let restored = hidden.chars().map(|c| SLOTS.restore(c)).collect::<String>();
+        assert_eq!(restored, message);
+
+        let (_, decoded) = SLOTS.find(&hidden).unwrap();
+        assert_eq!(decoded, Ok(data));
+        assert_eq!(SLOTS.find(&message), None);
+    }
+
+    #[test]
+    fn test_capacity() {
+        for len in 0..20 {
+            let slots = SLOTS.prefix_digits() + SLOTS.data_digits(len);
+            assert_eq!(SLOTS.capacity(slots), len);
+        }
+
+        assert_eq!(SLOTS.capacity(0), 0);
+    }
+}
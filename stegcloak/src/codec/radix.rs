@@ -0,0 +1,80 @@
+//! Radix-N conversion of 8 byte blocks, using every char of the alphabet as a digit
+
+/// Size of a full block in bytes
+pub(super) const BLOCK: usize = 8;
+
+/// Number of digits needed for a block of `bytes` bytes
+pub(super) fn digits(radix: usize, bytes: usize) -> usize {
+    let max = 1u128 << (8 * bytes);
+
+    let mut digits = 0;
+    let mut capacity = 1u128;
+    while capacity < max {
+        capacity *= radix as u128;
+        digits += 1;
+    }
+
+    digits
+}
+
+/// Number of bytes in a block which was encoded with `len` digits
+pub(super) fn bytes(radix: usize, len: usize) -> Option<usize> {
+    (1..=BLOCK).find(|&bytes| digits(radix, bytes) == len)
+}
+
+/// Encode a block of up to 8 bytes, least significant digit first
+pub(super) fn encode_block(block: &[u8], chars: &[char], out: &mut String) {
+    out.extend(encode_digits(block, chars.len()).map(|d| chars[d]));
+}
+
+/// Digits of a block of up to 8 bytes, least significant first
+pub(super) fn encode_digits(block: &[u8], radix: usize) -> impl Iterator<Item = usize> {
+    let mut value = [0u8; BLOCK];
+    value[..block.len()].copy_from_slice(block);
+    let mut value = u64::from_le_bytes(value);
+
+    (0..digits(radix, block.len())).map(move |_| {
+        let digit = value % radix as u64;
+        value /= radix as u64;
+        digit as usize
+    })
+}
+
+/// Decode the digits of a single block, which may be the short last one
+pub(super) fn decode_block(digits: &[usize], radix: usize, out: &mut Vec<u8>) -> Option<()> {
+    let bytes = bytes(radix, digits.len())?;
+
+    let value = digits
+        .iter()
+        .rev()
+        .fold(0u128, |value, &d| value * radix as u128 + d as u128);
+
+    // too large for the block, so it wasn't encoded by us
+    if value >= 1u128 << (8 * bytes) {
+        return None;
+    }
+
+    out.extend(&value.to_le_bytes()[..bytes]);
+
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digits() {
+        let lens = (1..=BLOCK).map(|b| digits(6, b)).collect::<Vec<_>>();
+        assert_eq!(lens, [4, 7, 10, 13, 16, 19, 22, 25]);
+
+        assert_eq!(bytes(6, 13), Some(4));
+        assert_eq!(bytes(6, 12), None);
+    }
+
+    #[test]
+    fn test_overflow() {
+        // 5555 in radix 6 is 6^4 - 1 = 1295, which doesn't fit into the single byte 4 digits hold
+        assert_eq!(decode_block(&[5, 5, 5, 5], 6, &mut Vec::new()), None);
+    }
+}
@@ -0,0 +1,162 @@
+//! Streams carried by substituting chars of the cover text, each slot char holding a digit
+//!
+//! A stream starts at the first slot with a prefix block holding a magic and the data length,
+//! since unchanged slots are digits too and can't mark the end of the stream. The rest is made
+//! of radix blocks, like [`super::Codec::Radix`] streams.
+
+use super::{radix, CodecError, FoundStream};
+
+/// Bytes of the data length in the prefix, u32 LE
+const LEN: usize = 4;
+
+pub(crate) struct Slots {
+    pub magic: &'static [u8],
+    pub radix: usize,
+    /// The digit a char stands for, or `None` if it isn't a slot
+    pub digit: fn(char) -> Option<usize>,
+    /// A slot char changed to stand for `digit`
+    pub with_digit: fn(char, usize) -> char,
+    /// A slot standing for digit 0, to spell out encoded digits as chars
+    pub zero: char,
+}
+
+impl Slots {
+    /// Number of slots the prefix block takes
+    pub fn prefix_digits(&self) -> usize {
+        radix::digits(self.radix, self.magic.len() + LEN)
+    }
+
+    /// Number of slots `len` bytes of data take, without the prefix
+    pub fn data_digits(&self, len: usize) -> usize {
+        // the length is untrusted while decoding
+        let full = (len / radix::BLOCK).saturating_mul(radix::digits(self.radix, radix::BLOCK));
+
+        match len % radix::BLOCK {
+            0 => full,
+            rest => full.saturating_add(radix::digits(self.radix, rest)),
+        }
+    }
+
+    /// Number of data bytes `slots` slots can carry
+    pub fn capacity(&self, slots: usize) -> usize {
+        let Some(slots) = slots.checked_sub(self.prefix_digits()) else {
+            return 0;
+        };
+
+        let block = radix::digits(self.radix, radix::BLOCK);
+        let rest = (1..radix::BLOCK)
+            .take_while(|&b| radix::digits(self.radix, b) <= slots % block)
+            .last()
+            .unwrap_or(0);
+
+        slots / block * radix::BLOCK + rest
+    }
+
+    /// The digits of the prefix and data, one per slot
+    pub fn encode(&self, data: &[u8]) -> Vec<usize> {
+        let mut prefix = self.magic.to_vec();
+        prefix.extend((data.len() as u32).to_le_bytes());
+
+        let mut digits = radix::encode_digits(&prefix, self.radix).collect::<Vec<_>>();
+        for block in data.chunks(radix::BLOCK) {
+            digits.extend(radix::encode_digits(block, self.radix));
+        }
+
+        digits
+    }
+
+    /// The data length a prefix block holds, or `None` if it isn't one
+    pub fn decode_prefix(&self, digits: &[usize]) -> Option<usize> {
+        let mut prefix = Vec::with_capacity(self.magic.len() + LEN);
+        radix::decode_block(digits, self.radix, &mut prefix)?;
+
+        let (magic, len) = prefix.split_at(self.magic.len());
+        (magic == self.magic).then(|| u32::from_le_bytes(len.try_into().unwrap()) as usize)
+    }
+
+    /// Number of slots the next block takes, with `remaining` bytes of data left
+    pub fn block_digits(&self, remaining: usize) -> usize {
+        radix::digits(self.radix, remaining.min(radix::BLOCK))
+    }
+
+    pub fn decode_block(&self, digits: &[usize], out: &mut Vec<u8>) -> Option<()> {
+        radix::decode_block(digits, self.radix, out)
+    }
+
+    /// Number of slots in `message`
+    pub fn count(&self, message: &str) -> usize {
+        message
+            .chars()
+            .filter(|&c| (self.digit)(c).is_some())
+            .count()
+    }
+
+    /// Change the slots of `message` to stand for `digits`, in order
+    ///
+    /// Returns `None` if there aren't enough slots
+    pub fn substitute(&self, message: &str, digits: &[usize]) -> Option<String> {
+        let mut digits = digits.iter();
+
+        let substituted = message
+            .chars()
+            .map(|c| match (self.digit)(c) {
+                Some(_) => digits.next().map_or(c, |&d| (self.with_digit)(c, d)),
+                None => c,
+            })
+            .collect();
+
+        digits.next().is_none().then_some(substituted)
+    }
+
+    /// A slot char changed back to the unchanged digit 0, anything else as is
+    pub fn restore(&self, c: char) -> char {
+        match (self.digit)(c) {
+            Some(_) => (self.with_digit)(c, 0),
+            None => c,
+        }
+    }
+
+    /// Find the stream in the slots of `string`, if there's one
+    ///
+    /// The range spans from the first to the last slot of the stream.
+    pub fn find(&self, string: &str) -> Option<FoundStream> {
+        let mut slots = string
+            .char_indices()
+            .filter_map(|(i, c)| (self.digit)(c).map(|d| (i, i + c.len_utf8(), d)));
+
+        let prefix = slots
+            .by_ref()
+            .take(self.prefix_digits())
+            .collect::<Vec<_>>();
+
+        if prefix.len() < self.prefix_digits() {
+            return None;
+        }
+
+        let digits = prefix.iter().map(|&(_, _, d)| d).collect::<Vec<_>>();
+        let len = self.decode_prefix(&digits)?;
+
+        let data = slots.take(self.data_digits(len)).collect::<Vec<_>>();
+        let start = prefix[0].0;
+        let end = data.last().unwrap_or(&prefix[prefix.len() - 1]).1;
+
+        if data.len() < self.data_digits(len) {
+            return Some((start..end, Err(CodecError::MalformedData)));
+        }
+
+        let digits = data.iter().map(|&(_, _, d)| d).collect::<Vec<_>>();
+
+        let mut bytes = Vec::with_capacity(len);
+        let mut rest = &digits[..];
+        while bytes.len() < len {
+            let (block, tail) = rest.split_at(self.block_digits(len - bytes.len()));
+            if self.decode_block(block, &mut bytes).is_none() {
+                return Some((start..end, Err(CodecError::MalformedData)));
+            }
+
+            rest = tail;
+        }
+
+        Some((start..end, Ok(bytes)))
+    }
+}
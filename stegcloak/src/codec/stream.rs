@@ -0,0 +1,900 @@
+//! Incremental zwc encoding and decoding over `std::io`, with bounded memory
+
+use std::io::{self, BufRead, Read, Write};
+
+use itertools::Itertools as _;
+
+use super::{
+    framed, is_stream_char, radix, tag, variation, Alphabet, Codec, CodecError, Profile, BASE,
+    COUNT_DIGITS, FRAGMENTED, FRAMED, SUBSTITUTING,
+};
+use crate::header;
+
+/// Amount of data the encoder looks at to pick the most common doubled pairs
+const LOOKAHEAD: usize = 8 * 1024;
+
+/// Encodes binary data written to it into zwc characters, written to the inner writer
+///
+/// With [`Codec::Base4`], the dyn chars are picked from the first 8KiB of data, so for data up
+/// to that size the output is the same as [`super::encode_with`]. Codecs which
+/// [`Codec::substitutes`] hold everything back, since their prefix records the data length. Call [`Encoder::finish`] when
+/// done.
+pub struct Encoder<W: Write> {
+    inner: W,
+    alphabet: Alphabet,
+    codec: Codec,
+    // radix or tag data short of a full block, or all substituted data
+    block: Vec<u8>,
+    // data held back until the dyn chars are picked
+    lookahead: Option<Vec<u8>>,
+    // data char each dyn char stands in for when doubled
+    dyns: [usize; 2],
+    // last data char, in case it's doubled by the next one
+    pending: Option<usize>,
+    out: String,
+}
+
+impl<W: Write> Encoder<W> {
+    pub fn new(inner: W, alphabet: Alphabet) -> Self {
+        Self::with_codec(inner, Codec::Base4, alphabet)
+    }
+
+    /// Encode with a specific codec
+    pub fn with_codec(inner: W, codec: Codec, alphabet: Alphabet) -> Self {
+        let prefix = codec.prefix(&alphabet);
+
+        Self {
+            inner,
+            alphabet,
+            codec,
+            block: Vec::with_capacity(radix::BLOCK),
+            lookahead: (codec == Codec::Base4).then(Vec::new),
+            dyns: [0; 2],
+            pending: None,
+            out: prefix,
+        }
+    }
+
+    /// Write out everything that's left and return the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.start()?;
+
+        if !self.block.is_empty() {
+            match self.codec {
+                Codec::TagCharacters => tag::encode_block(&self.block, &mut self.out),
+                Codec::Whitespace | Codec::Homoglyphs => {
+                    let slots = self.codec.slots().unwrap();
+                    self.out.extend(
+                        slots
+                            .encode(&self.block)
+                            .into_iter()
+                            .map(|d| (slots.with_digit)(slots.zero, d)),
+                    );
+                }
+                _ => radix::encode_block(&self.block, &self.alphabet.0, &mut self.out),
+            }
+        }
+
+        if let Some(p) = self.pending.take() {
+            self.out.push(self.alphabet.0[p]);
+        }
+
+        self.flush_out()?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+
+    /// Pick the dyn chars and encode the held back data
+    fn start(&mut self) -> io::Result<()> {
+        let Some(lookahead) = self.lookahead.take() else {
+            return Ok(());
+        };
+
+        let mut counts = [0usize; BASE];
+        for byte in &lookahead {
+            for b in (0..=6).step_by(2) {
+                counts[((byte >> b) & 0b11) as usize] += 1;
+            }
+        }
+
+        // same choice as `encode_with`, the 2 most common chars
+        let first = counts.iter().position_max().unwrap();
+        counts[first] = 0;
+        let second = counts.iter().position_max().unwrap();
+
+        self.dyns = [first, second];
+        self.out.push(self.alphabet.0[first]);
+        self.out.push(self.alphabet.0[second]);
+
+        self.encode(&lookahead);
+        self.flush_out()
+    }
+
+    fn encode(&mut self, data: &[u8]) {
+        let zwc = &self.alphabet.0;
+
+        // LE
+        for byte in data {
+            for b in (0..=6).step_by(2) {
+                let c = ((byte >> b) & 0b11) as usize;
+
+                match self.pending.take() {
+                    Some(p) if p == c => {
+                        let dyn_char = if self.dyns[0] == p { BASE } else { BASE + 1 };
+                        self.out.push(zwc[dyn_char]);
+                    }
+
+                    pending => {
+                        if let Some(p) = pending {
+                            self.out.push(zwc[p]);
+                        }
+
+                        if self.dyns.contains(&c) {
+                            self.pending = Some(c);
+                        } else {
+                            self.out.push(zwc[c]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn flush_out(&mut self) -> io::Result<()> {
+        self.inner.write_all(self.out.as_bytes())?;
+        self.out.clear();
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.codec.substitutes() {
+            self.block.extend(buf);
+            return Ok(buf.len());
+        }
+
+        if self.codec == Codec::VariationSelectors {
+            self.out.extend(buf.iter().map(|&b| variation::to_char(b)));
+            self.flush_out()?;
+
+            return Ok(buf.len());
+        }
+
+        if self.codec == Codec::TagCharacters {
+            self.block.extend(buf);
+
+            let full = self.block.len() / tag::BLOCK * tag::BLOCK;
+            for block in self.block[..full].chunks(tag::BLOCK) {
+                tag::encode_block(block, &mut self.out);
+            }
+
+            self.block.drain(..full);
+            self.flush_out()?;
+
+            return Ok(buf.len());
+        }
+
+        if self.codec == Codec::Radix {
+            self.block.extend(buf);
+
+            let full = self.block.len() / radix::BLOCK * radix::BLOCK;
+            for block in self.block[..full].chunks(radix::BLOCK) {
+                radix::encode_block(block, &self.alphabet.0, &mut self.out);
+            }
+
+            self.block.drain(..full);
+            self.flush_out()?;
+
+            return Ok(buf.len());
+        }
+
+        match &mut self.lookahead {
+            Some(lookahead) => {
+                lookahead.extend(buf);
+                if lookahead.len() >= LOOKAHEAD {
+                    self.start()?;
+                }
+            }
+
+            None => {
+                self.encode(buf);
+                self.flush_out()?;
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // held back data can't be written before the dyn chars are picked
+        self.inner.flush()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum State {
+    Searching,
+    Started { dyn1: char, dyn2: char },
+    Radix,
+    Framed,
+    Variation,
+    Tag,
+    Substituted { codec: Codec, remaining: usize },
+    Done,
+}
+
+/// Decodes the first zwc stream in the text read from the inner reader
+///
+/// Errors are returned as [`io::ErrorKind::InvalidData`] wrapping a [`CodecError`]
+pub struct Decoder<R: BufRead> {
+    inner: R,
+    alphabet: Alphabet,
+    // whether the alphabet is detected from the built-in profiles
+    detect: bool,
+    state: State,
+    // second half of an expanded dyn char
+    pending: Option<u8>,
+    // decoded radix block, and how much of it was read
+    block: Vec<u8>,
+    block_pos: usize,
+    // digits of the first chars each codec substitutes, until they turn out to be its prefix
+    // or not
+    prefixes: Vec<(Codec, Vec<usize>)>,
+    // fragments left after the current one, if the stream is fragmented
+    fragments: Option<usize>,
+}
+
+impl<R: BufRead> Decoder<R> {
+    pub fn new(inner: R, alphabet: Alphabet) -> Self {
+        Self {
+            inner,
+            alphabet,
+            detect: false,
+            state: State::Searching,
+            pending: None,
+            block: Vec::with_capacity(radix::BLOCK),
+            block_pos: 0,
+            prefixes: SUBSTITUTING.map(|codec| (codec, Vec::new())).to_vec(),
+            fragments: None,
+        }
+    }
+
+    /// Decode a stream of any built-in [`Profile`], detected from the stream itself
+    pub fn detect(inner: R) -> Self {
+        Self {
+            detect: true,
+            ..Self::new(inner, Alphabet::default())
+        }
+    }
+
+    /// Skip to the start of the stream and read the 2 dyn chars
+    fn start(&mut self) -> io::Result<()> {
+        let dyn1 = loop {
+            match self.read_data_char()? {
+                Some(variation::MARKER) => return self.start_variation(),
+                Some(tag::MARKER) => {
+                    self.state = State::Tag;
+                    return Ok(());
+                }
+                Some(c) if self.substituted_prefix(c) => return Ok(()),
+                Some(c) if self.detect && self.detect_marker(c) => break c,
+                Some(c) if self.alphabet.contains(c) => break c,
+                Some(_) => (),
+                None => return Err(error(CodecError::ZwcDataNotfound)),
+            }
+        };
+
+        let dyn2 = self.read_second()?;
+        let zwc = &self.alphabet.0;
+
+        // base4 streams never start with a dyn char
+        if dyn1 == zwc[BASE] && dyn2 == zwc[FRAGMENTED] {
+            return self.start_fragments();
+        }
+
+        if dyn1 == zwc[BASE] && dyn2 == zwc[FRAMED] {
+            // handed out like the leftovers of a radix block
+            self.block.clear();
+            self.block.push(header::FRAMED);
+            self.block_pos = 0;
+
+            self.state = State::Framed;
+            return Ok(());
+        }
+
+        self.state = if dyn1 == zwc[BASE] {
+            let codec = zwc
+                .iter()
+                .position(|&c| c == dyn2)
+                .and_then(Codec::from_id)
+                .ok_or_else(|| error(CodecError::UnknownCodec))?;
+
+            match codec {
+                Codec::Base4 => {
+                    let Some(dyn1) = self.read_data_char()? else {
+                        return Err(error(CodecError::MalformedData));
+                    };
+
+                    let dyn2 = self.read_second()?;
+                    State::Started { dyn1, dyn2 }
+                }
+                Codec::Radix => State::Radix,
+                Codec::VariationSelectors
+                | Codec::TagCharacters
+                | Codec::Whitespace
+                | Codec::Homoglyphs => unreachable!("has no id"),
+            }
+        } else {
+            State::Started { dyn1, dyn2 }
+        };
+
+        Ok(())
+    }
+
+    /// Collect the digits of the first substituted chars, starting a stream once they are its
+    /// prefix
+    fn substituted_prefix(&mut self, c: char) -> bool {
+        for (codec, digits) in &mut self.prefixes {
+            let slots = codec.slots().unwrap();
+            let Some(digit) = (slots.digit)(c) else {
+                continue;
+            };
+
+            digits.push(digit);
+            if digits.len() < slots.prefix_digits() {
+                return false;
+            }
+
+            if let Some(remaining) = slots.decode_prefix(digits) {
+                self.state = State::Substituted {
+                    codec: *codec,
+                    remaining,
+                };
+                return true;
+            }
+        }
+
+        self.prefixes
+            .retain(|(codec, digits)| digits.len() < codec.slots().unwrap().prefix_digits());
+        false
+    }
+
+    /// Next byte of a substituted stream, decoding a block at a time
+    fn next_substituted_byte(&mut self, codec: Codec, remaining: usize) -> io::Result<Option<u8>> {
+        if remaining == 0 {
+            self.state = State::Done;
+            return Ok(None);
+        }
+
+        let slots = codec.slots().unwrap();
+        let block_len = slots.block_digits(remaining);
+
+        let mut digits = Vec::with_capacity(block_len);
+        while digits.len() < block_len {
+            match self.read_data_char()? {
+                Some(c) => digits.extend((slots.digit)(c)),
+                // the prefix promised more
+                None => return Err(error(CodecError::MalformedData)),
+            }
+        }
+
+        self.block.clear();
+        self.block_pos = 1;
+        slots
+            .decode_block(&digits, &mut self.block)
+            .ok_or_else(|| error(CodecError::MalformedData))?;
+
+        self.state = State::Substituted {
+            codec,
+            remaining: remaining - self.block.len(),
+        };
+
+        Ok(Some(self.block[0]))
+    }
+
+    /// Read the fragment count of a fragmented stream, and start the stream it holds
+    fn start_fragments(&mut self) -> io::Result<()> {
+        // fragments are never nested
+        if self.fragments.is_some() {
+            return Err(error(CodecError::MalformedData));
+        }
+
+        let zwc = self.alphabet.0;
+
+        let mut count = 0;
+        for i in 0..COUNT_DIGITS {
+            let digit = self
+                .read_data_char()?
+                .and_then(|c| zwc.iter().position(|&z| z == c))
+                .ok_or_else(|| error(CodecError::MalformedData))?;
+
+            count += digit * zwc.len().pow(i);
+        }
+
+        self.fragments = Some(count);
+        self.start()
+    }
+
+    /// Read the next char, skipping the text between the fragments of a fragmented stream
+    fn read_data_char(&mut self) -> io::Result<Option<char>> {
+        let c = read_char(&mut self.inner)?;
+
+        let Some(fragments) = self.fragments.filter(|&f| f > 0) else {
+            return Ok(c);
+        };
+
+        match c {
+            Some(c) if is_stream_char(c, &self.alphabet) => Ok(Some(c)),
+            _ => loop {
+                match read_char(&mut self.inner)? {
+                    Some(c) if is_stream_char(c, &self.alphabet) => {
+                        self.fragments = Some(fragments - 1);
+                        return Ok(Some(c));
+                    }
+                    Some(_) => (),
+                    // the count promised more
+                    None => return Err(error(CodecError::MalformedData)),
+                }
+            },
+        }
+    }
+
+    /// Start a variation selector stream, right after its marker
+    fn start_variation(&mut self) -> io::Result<()> {
+        // minimum 1 byte of data needed
+        let Some(byte) = self.read_data_char()?.and_then(variation::from_char) else {
+            return Err(error(CodecError::MalformedData));
+        };
+
+        // handed out like the leftovers of a radix block
+        self.block.clear();
+        self.block.push(byte);
+        self.block_pos = 0;
+
+        self.state = State::Variation;
+        Ok(())
+    }
+
+    /// Switch to the profile `c` is the marker of, if any
+    fn detect_marker(&mut self, c: char) -> bool {
+        let profile = Profile::ALL
+            .into_iter()
+            .find(|p| *p != Profile::MaxCompatibility && p.alphabet().0[BASE] == c);
+
+        if let Some(profile) = profile {
+            self.alphabet = profile.alphabet();
+        }
+
+        profile.is_some()
+    }
+
+    /// Read the second char of the stream, making sure there's data after it
+    fn read_second(&mut self) -> io::Result<char> {
+        let Some(dyn2) = self.read_data_char()? else {
+            return Err(error(CodecError::MalformedData));
+        };
+
+        // minimum 2 positional chars + data needed
+        if self.inner.fill_buf()?.is_empty() {
+            return Err(error(CodecError::MalformedData));
+        }
+
+        Ok(dyn2)
+    }
+
+    /// Next byte of a radix stream, decoding a block at a time
+    fn next_radix_byte(&mut self) -> io::Result<Option<u8>> {
+        if let Some(&byte) = self.block.get(self.block_pos) {
+            self.block_pos += 1;
+            return Ok(Some(byte));
+        }
+
+        let zwc = self.alphabet.0;
+        let block_len = radix::digits(zwc.len(), radix::BLOCK);
+
+        let mut digits = Vec::with_capacity(block_len);
+        while digits.len() < block_len {
+            let digit = self
+                .read_data_char()?
+                .and_then(|c| zwc.iter().position(|&z| z == c));
+
+            match digit {
+                Some(digit) => digits.push(digit),
+                // end of stream
+                None => {
+                    self.state = State::Done;
+                    break;
+                }
+            }
+        }
+
+        if digits.is_empty() {
+            return Ok(None);
+        }
+
+        self.block.clear();
+        self.block_pos = 1;
+        radix::decode_block(&digits, zwc.len(), &mut self.block)
+            .ok_or_else(|| error(CodecError::MalformedData))?;
+
+        Ok(Some(self.block[0]))
+    }
+
+    /// Next byte of an error corrected stream, decoding a run of frames at a time
+    fn next_framed_byte(&mut self) -> io::Result<Option<u8>> {
+        let zwc = self.alphabet.0;
+        let max_run = framed::digits() * framed::MAX_RUN;
+
+        // a run may hold no frame, after a doubled separator
+        while self.block_pos == self.block.len() {
+            if let State::Done = self.state {
+                return Ok(None);
+            }
+
+            let mut run = Vec::new();
+            while run.len() < max_run {
+                let digit = self
+                    .read_data_char()?
+                    .and_then(|c| zwc.iter().position(|&z| z == c));
+
+                match digit {
+                    Some(framed::SEPARATOR) => break,
+                    Some(digit) => run.push(digit),
+                    // end of stream
+                    None => {
+                        self.state = State::Done;
+                        break;
+                    }
+                }
+            }
+
+            self.block.clear();
+            self.block_pos = 0;
+            framed::decode(&run, &mut self.block);
+        }
+
+        self.block_pos += 1;
+        Ok(Some(self.block[self.block_pos - 1]))
+    }
+
+    /// Next byte of a tag stream, decoding a block at a time
+    fn next_tag_byte(&mut self) -> io::Result<Option<u8>> {
+        if let Some(&byte) = self.block.get(self.block_pos) {
+            self.block_pos += 1;
+            return Ok(Some(byte));
+        }
+
+        let mut values = Vec::with_capacity(tag::BLOCK_CHARS);
+        while values.len() < tag::BLOCK_CHARS {
+            match self.read_data_char()?.and_then(tag::from_char) {
+                Some(value) => values.push(value),
+                // end of stream
+                None => {
+                    self.state = State::Done;
+                    break;
+                }
+            }
+        }
+
+        if values.is_empty() {
+            // minimum 1 byte of data needed
+            if self.block.is_empty() {
+                return Err(error(CodecError::MalformedData));
+            }
+
+            return Ok(None);
+        }
+
+        self.block.clear();
+        self.block_pos = 1;
+        tag::decode_block(&values, &mut self.block)
+            .ok_or_else(|| error(CodecError::MalformedData))?;
+
+        Ok(Some(self.block[0]))
+    }
+
+    /// Next 2 bits of data, or `None` at the end of the stream
+    fn next_bits(&mut self, chunk_start: bool) -> io::Result<Option<u8>> {
+        let State::Started { dyn1, dyn2 } = self.state else {
+            return Ok(None);
+        };
+
+        if let Some(bits) = self.pending.take() {
+            return Ok(Some(bits));
+        }
+
+        let zwc = self.alphabet.0;
+
+        let Some(c) = self.read_data_char()? else {
+            return Ok(None);
+        };
+
+        if !self.alphabet.contains(c) {
+            // detect end of stream, since the next chunk doesn't start with zwc anymore
+            if chunk_start {
+                return Ok(None);
+            }
+
+            // however, if a chunk is cut short, our stream is broken
+            return Err(error(CodecError::MalformedData));
+        }
+
+        let dyns = &zwc[BASE..];
+
+        // dyn chars stand for a doubled data char, unless they're unused sentinels
+        let (c, doubled) = match c {
+            _ if c == zwc[BASE] && !dyns.contains(&dyn1) => (dyn1, true),
+            _ if c == zwc[BASE + 1] && !dyns.contains(&dyn2) => (dyn2, true),
+            _ => (c, false),
+        };
+
+        let Some(bits) = zwc[..BASE].iter().position(|&z| z == c) else {
+            // a dyn char which wasn't expanded
+            return Err(error(CodecError::MalformedData));
+        };
+
+        let bits = bits as u8;
+        if doubled {
+            self.pending = Some(bits);
+        }
+
+        Ok(Some(bits))
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        if let State::Searching = self.state {
+            self.start()?;
+        }
+
+        if let State::Radix = self.state {
+            return self.next_radix_byte();
+        }
+
+        if let State::Tag = self.state {
+            return self.next_tag_byte();
+        }
+
+        if let State::Framed = self.state {
+            return self.next_framed_byte();
+        }
+
+        // leftovers of the last radix, tag or substituted block
+        if self.block_pos < self.block.len() {
+            return self.next_radix_byte();
+        }
+
+        if let State::Substituted { codec, remaining } = self.state {
+            return self.next_substituted_byte(codec, remaining);
+        }
+
+        if let State::Variation = self.state {
+            let byte = self.read_data_char()?.and_then(variation::from_char);
+            if byte.is_none() {
+                self.state = State::Done;
+            }
+
+            return Ok(byte);
+        }
+
+        let mut byte = 0u8;
+        for i in 0..4 {
+            let Some(bits) = self.next_bits(i == 0)? else {
+                self.state = State::Done;
+                return Ok(None);
+            };
+
+            // LE
+            byte |= bits << (i * 2);
+        }
+
+        Ok(Some(byte))
+    }
+}
+
+impl<R: BufRead> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n < buf.len() {
+            let Some(byte) = self.next_byte()? else {
+                break;
+            };
+
+            buf[n] = byte;
+            n += 1;
+        }
+
+        Ok(n)
+    }
+}
+
+fn error(e: CodecError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Read a single utf8 char
+fn read_char(reader: &mut impl BufRead) -> io::Result<Option<char>> {
+    let Some(&first) = reader.fill_buf()?.first() else {
+        return Ok(None);
+    };
+
+    let len = match first {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid utf8")),
+    };
+
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes[..len])?;
+
+    std::str::from_utf8(&bytes[..len])
+        .ok()
+        .and_then(|s| s.chars().next())
+        .map(Some)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid utf8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{decode, encode, substitute};
+
+    #[test]
+    fn test_encoder_matches_encode() {
+        let data = (0..=255).collect::<Vec<u8>>();
+
+        let mut encoder = Encoder::new(Vec::new(), Alphabet::default());
+        for chunk in data.chunks(7) {
+            encoder.write_all(chunk).unwrap();
+        }
+
+        let encoded = String::from_utf8(encoder.finish().unwrap()).unwrap();
+        assert_eq!(encoded, encode(&data));
+    }
+
+    #[test]
+    fn test_roundtrip_large() {
+        let data = (0..100_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+
+        let mut encoder = Encoder::new(b"cover ".to_vec(), Alphabet::default());
+        encoder.write_all(&data).unwrap();
+        let mut encoded = encoder.finish().unwrap();
+        encoded.extend(b"text");
+
+        // the regular decoder understands it too
+        assert_eq!(
+            decode(std::str::from_utf8(&encoded).unwrap()).unwrap(),
+            data
+        );
+
+        // tiny buffer, so chars get split across reads
+        let reader = io::BufReader::with_capacity(3, &encoded[..]);
+        let mut decoded = Vec::new();
+        Decoder::new(reader, Alphabet::default())
+            .read_to_end(&mut decoded)
+            .unwrap();
+
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_radix() {
+        let data = (0..10_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+
+        let mut encoder = Encoder::with_codec(Vec::new(), Codec::Radix, Alphabet::default());
+        for chunk in data.chunks(7) {
+            encoder.write_all(chunk).unwrap();
+        }
+
+        let encoded = String::from_utf8(encoder.finish().unwrap()).unwrap();
+        assert_eq!(
+            encoded,
+            crate::codec::encode_as(&data, Codec::Radix, &Alphabet::default())
+        );
+
+        let text = format!("cover {encoded} text");
+        let reader = io::BufReader::with_capacity(3, text.as_bytes());
+        let mut decoded = Vec::new();
+        Decoder::new(reader, Alphabet::default())
+            .read_to_end(&mut decoded)
+            .unwrap();
+
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_framed() {
+        let data = (0..40 * crate::fec::FRAME as u32)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        let data = [&[header::FRAMED], &data[..]].concat();
+        let encoded = crate::codec::encode_framed(&data, &Alphabet::default());
+        let separator = Alphabet::default().0[framed::SEPARATOR];
+
+        // a dropped char, and runs of frames longer than a decoder holds on to
+        let mut chars = encoded.chars().collect::<Vec<_>>();
+        chars.remove(chars.len() / 2);
+        let edited = [
+            encoded.clone(),
+            String::from_iter(chars),
+            encoded.replace(separator, ""),
+        ];
+
+        for encoded in edited {
+            let text = format!("cover {encoded} text");
+            let reader = io::BufReader::with_capacity(3, text.as_bytes());
+            let mut decoded = Vec::new();
+            Decoder::new(reader, Alphabet::default())
+                .read_to_end(&mut decoded)
+                .unwrap();
+
+            assert_eq!(decoded.len(), data.len());
+            assert_eq!(decoded, decode(&text).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_detect_profile() {
+        let data = (0..10_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+
+        for profile in Profile::ALL {
+            for codec in [Codec::Base4, Codec::Radix, Codec::TagCharacters] {
+                let mut encoder = Encoder::with_codec(Vec::new(), codec, profile.alphabet());
+                encoder.write_all(&data).unwrap();
+
+                let encoded = String::from_utf8(encoder.finish().unwrap()).unwrap();
+                assert_eq!(
+                    encoded,
+                    crate::codec::encode_as(&data, codec, &profile.alphabet())
+                );
+
+                let text = format!("cover {encoded} text");
+                let mut decoded = Vec::new();
+                Decoder::detect(text.as_bytes())
+                    .read_to_end(&mut decoded)
+                    .unwrap();
+
+                assert_eq!(decoded, data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_substituted() {
+        let data = (0..100).collect::<Vec<u8>>();
+
+        for codec in SUBSTITUTING {
+            let mut encoder = Encoder::with_codec(Vec::new(), codec, Alphabet::default());
+            encoder.write_all(&data).unwrap();
+            let encoded = String::from_utf8(encoder.finish().unwrap()).unwrap();
+
+            let text = substitute(&"code ".repeat(500), &encoded, codec).unwrap();
+            let mut decoded = Vec::new();
+            Decoder::detect(text.as_bytes())
+                .read_to_end(&mut decoded)
+                .unwrap();
+
+            assert_eq!(decoded, data);
+        }
+    }
+
+    #[test]
+    fn test_decoder_errors() {
+        let mut decoder = Decoder::new(&b"cover text"[..], Alphabet::default());
+        let e = decoder.read_to_end(&mut Vec::new()).unwrap_err();
+
+        assert_eq!(
+            e.into_inner().unwrap().downcast::<CodecError>().unwrap(),
+            Box::new(CodecError::ZwcDataNotfound)
+        );
+
+        let text =
+            "cover \u{2062}\u{2062}\u{2062}\u{2062}\u{2062}\u{2062}\u{2062}a\u{2062}\u{2062}";
+        let mut decoder = Decoder::new(text.as_bytes(), Alphabet::default());
+        assert!(decoder.read_to_end(&mut Vec::new()).is_err());
+    }
+}
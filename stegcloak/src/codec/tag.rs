@@ -0,0 +1,102 @@
+//! 7 bits per char, using all 128 code points of the tag block
+
+use super::CodecError;
+
+/// Size of a full block in bytes, which takes exactly 8 chars
+pub(super) const BLOCK: usize = 7;
+
+/// Number of chars in a full block
+pub(super) const BLOCK_CHARS: usize = 8;
+
+const TAGS: u32 = 0xe0000;
+
+/// First char of every stream, the deprecated language tag
+///
+/// Unlike cancel tag, it's not part of emoji tag sequences such as subdivision flags.
+pub(super) const MARKER: char = '\u{e0001}';
+
+/// The 7 bit value a tag char stands for
+pub(super) fn from_char(c: char) -> Option<u8> {
+    match c as u32 {
+        c @ 0xe0000..=0xe007f => Some((c - TAGS) as u8),
+        _ => None,
+    }
+}
+
+/// Encode a block of up to 7 bytes, least significant bits first
+pub(super) fn encode_block(block: &[u8], out: &mut String) {
+    let mut value = [0u8; 8];
+    value[..block.len()].copy_from_slice(block);
+    let value = u64::from_le_bytes(value);
+
+    for i in 0..(block.len() * 8).div_ceil(7) {
+        let bits = (value >> (i * 7)) & 0x7f;
+        out.push(char::from_u32(TAGS + bits as u32).unwrap());
+    }
+}
+
+/// Decode the values of a single block, which may be the short last one
+pub(super) fn decode_block(values: &[u8], out: &mut Vec<u8>) -> Option<()> {
+    let bytes = values.len() * 7 / 8;
+    // a single char can't hold a byte
+    if bytes == 0 || values.len() > BLOCK_CHARS {
+        return None;
+    }
+
+    let value = values
+        .iter()
+        .enumerate()
+        .fold(0u64, |value, (i, &v)| value | (v as u64) << (i * 7));
+
+    out.extend(&value.to_le_bytes()[..bytes]);
+    Some(())
+}
+
+/// Decode the stream at the start of `string`, which starts with the marker
+///
+/// Returns the data and the length of the stream in bytes
+pub(super) fn decode(string: &str) -> Result<(Vec<u8>, usize), CodecError> {
+    let data = &string[MARKER.len_utf8()..];
+
+    let end = data.find(|c| from_char(c).is_none()).unwrap_or(data.len());
+
+    let values = data[..end]
+        .chars()
+        .filter_map(from_char)
+        .collect::<Vec<_>>();
+
+    // minimum 1 byte of data needed
+    if values.is_empty() {
+        return Err(CodecError::MalformedData);
+    }
+
+    let mut bytes = Vec::with_capacity(values.len() * 7 / 8);
+    for block in values.chunks(BLOCK_CHARS) {
+        decode_block(block, &mut bytes).ok_or(CodecError::MalformedData)?;
+    }
+
+    Ok((bytes, MARKER.len_utf8() + end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks() {
+        for len in 1..=BLOCK {
+            let data = vec![0xa5; len];
+
+            let mut encoded = String::new();
+            encode_block(&data, &mut encoded);
+            assert_eq!(encoded.chars().count(), (len * 8).div_ceil(7));
+
+            let values = encoded.chars().filter_map(from_char).collect::<Vec<_>>();
+            let mut decoded = Vec::new();
+            decode_block(&values, &mut decoded).unwrap();
+            assert_eq!(decoded, data);
+        }
+
+        assert_eq!(decode_block(&[1], &mut Vec::new()), None);
+    }
+}
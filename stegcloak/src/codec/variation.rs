@@ -0,0 +1,61 @@
+//! One variation selector per byte, from the 256 selectors unicode has
+
+use super::CodecError;
+
+/// Selectors 1-16, for bytes 0-15
+const BMP: u32 = 0xfe00;
+/// Selectors 17-256, for bytes 16-255
+const SUPPLEMENT: u32 = 0xe0100 - 16;
+
+/// First char of every stream, the last selector
+///
+/// Base4 and radix streams never contain it, so it tells the codec apart.
+pub(super) const MARKER: char = '\u{e01ef}';
+
+/// The selector standing for `byte`
+pub(super) fn to_char(byte: u8) -> char {
+    let base = if byte < 16 { BMP } else { SUPPLEMENT };
+    char::from_u32(base + byte as u32).unwrap()
+}
+
+/// The byte a selector stands for
+pub(super) fn from_char(c: char) -> Option<u8> {
+    match c as u32 {
+        c @ 0xfe00..=0xfe0f => Some((c - BMP) as u8),
+        c @ 0xe0100..=0xe01ef => Some((c - SUPPLEMENT) as u8),
+        _ => None,
+    }
+}
+
+/// Decode the stream at the start of `string`, which starts with the marker
+///
+/// Returns the data and the length of the stream in bytes
+pub(super) fn decode(string: &str) -> Result<(Vec<u8>, usize), CodecError> {
+    let data = &string[MARKER.len_utf8()..];
+
+    let end = data.find(|c| from_char(c).is_none()).unwrap_or(data.len());
+
+    // minimum 1 byte of data needed
+    if end == 0 {
+        return Err(CodecError::MalformedData);
+    }
+
+    let bytes = data[..end].chars().filter_map(from_char).collect();
+
+    Ok((bytes, MARKER.len_utf8() + end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chars() {
+        for byte in 0..=255 {
+            assert_eq!(from_char(to_char(byte)), Some(byte));
+        }
+
+        assert_eq!(to_char(255), MARKER);
+        assert_eq!(from_char('a'), None);
+    }
+}
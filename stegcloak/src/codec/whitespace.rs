@@ -0,0 +1,53 @@
+//! Radix-8 digits in the choice of space chars, replacing the spaces of the cover text
+
+use super::slots::Slots;
+
+/// Spaces about as wide as a regular one, in digit order
+const SPACES: [char; 8] = [
+    ' ', '\u{a0}', '\u{2004}', '\u{2005}', '\u{2008}', '\u{2009}', '\u{202f}', '\u{205f}',
+];
+
+pub(super) const SLOTS: Slots = Slots {
+    magic: &[0xc5],
+    radix: SPACES.len(),
+    digit,
+    with_digit: |_, d| SPACES[d],
+    zero: ' ',
+};
+
+/// The digit a space stands for
+fn digit(c: char) -> Option<usize> {
+    SPACES.iter().position(|&s| s == c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let message = "a ".repeat(100);
+
+        for len in [0, 1, 8, 13] {
+            let data = (0..len as u8).collect::<Vec<_>>();
+
+            let digits = SLOTS.encode(&data);
+            assert_eq!(digits.len(), SLOTS.prefix_digits() + SLOTS.data_digits(len));
+
+            let hidden = SLOTS.substitute(&message, &digits).unwrap();
+            assert_eq!(hidden.chars().count(), message.chars().count());
+
+            let (range, decoded) = SLOTS.find(&hidden).unwrap();
+            assert_eq!(decoded, Ok(data));
+            assert_eq!(range.start, 1);
+        }
+
+        assert_eq!(SLOTS.find(&message), None);
+    }
+
+    #[test]
+    fn test_not_enough_spaces() {
+        let digits = SLOTS.encode(&[1, 2, 3]);
+        assert_eq!(SLOTS.substitute("too few spaces", &digits), None);
+    }
+}
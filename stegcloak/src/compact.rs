@@ -1,37 +1,421 @@
 use std::{
-    io::{self, Write},
-    string::FromUtf8Error,
+    io::{self, Read, Write},
+    mem,
 };
 
 use flate2::{
+    read,
     write::{DeflateDecoder, DeflateEncoder},
     Compression,
 };
 
-/// Compress a string using deflate
-pub fn compress(data: &str) -> Result<Vec<u8>, DeCompressError> {
-    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
-    encoder.write_all(data.as_bytes())?;
+use zeroize::{Zeroize as _, Zeroizing};
+
+use crate::header;
+
+mod dictionary;
+
+pub use dictionary::{Dictionary, MAX_ENTRIES, MAX_INPUT, PRESET};
+
+/// Default cap on the size of a decompressed secret, and of a decoded stream, 16 MiB
+///
+/// Compressed data can expand a thousandfold, so untrusted text could otherwise exhaust memory.
+pub const MAX_SIZE: usize = 16 * 1024 * 1024;
+
+/// A scheme secrets can be compressed with, recorded in the header so revealing knows it
+///
+/// Short secrets grow with deflate, so [`compress_smallest`] tries several and keeps whichever
+/// result is smallest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Compressor {
+    /// Kept as is
+    Store,
+    /// Deflate at a level from 0 (store only) to 9 (best)
+    Deflate { level: u32 },
+    /// ASCII text packed into 7 bits per char, which only fits text without NUL chars
+    SmallText,
+    /// Codebook of common fragments, [`Dictionary::preset`] or a custom one
+    Dictionary(Dictionary),
+}
+
+impl Compressor {
+    /// The id recorded in the header
+    pub fn compression(&self) -> header::Compression {
+        match self {
+            Compressor::Store => header::Compression::Store,
+            Compressor::Deflate { .. } => header::Compression::Deflate,
+            Compressor::SmallText => header::Compression::SmallText,
+            Compressor::Dictionary(_) => header::Compression::Dictionary,
+        }
+    }
+
+    /// Compress binary data, or `None` if the scheme can't represent it
+    pub fn compress(&self, data: &[u8]) -> Option<Vec<u8>> {
+        match self {
+            Compressor::Store => Some(data.to_vec()),
+            Compressor::Deflate { level } => compress_bytes_with(data, *level).ok(),
+            Compressor::SmallText => pack_ascii(data),
+            Compressor::Dictionary(dictionary) => dictionary.compress(data),
+        }
+    }
+}
+
+/// Compress binary data with whichever of `compressors` gives the smallest result
+///
+/// Ties go to the earlier compressor, and [`Compressor::Store`] is the fallback if none fits.
+/// The results are wiped from memory once dropped, the ones not picked right away.
+pub fn compress_smallest(
+    data: &[u8],
+    compressors: &[Compressor],
+) -> (header::Compression, Zeroizing<Vec<u8>>) {
+    compressors
+        .iter()
+        .filter_map(|compressor| {
+            let compressed = Zeroizing::new(compressor.compress(data)?);
+            Some((compressor.compression(), compressed))
+        })
+        .min_by_key(|(_, compressed)| compressed.len())
+        .unwrap_or_else(|| (header::Compression::Store, Zeroizing::new(data.to_vec())))
+}
 
-    let data = encoder.finish()?;
+/// Decompress binary data compressed with the scheme recorded in the header
+///
+/// Data compressed with a custom [`Dictionary`] needs `custom` to be the same dictionary.
+/// Fails with [`DeCompressError::TooLarge`] instead of decompressing more than `max_size` bytes.
+pub fn decompress_as(
+    data: &[u8],
+    compression: header::Compression,
+    custom: Option<&Dictionary>,
+    max_size: usize,
+) -> Result<Zeroizing<Vec<u8>>, DeCompressError> {
+    let data = match compression {
+        header::Compression::Deflate => Zeroizing::new(decompress_bytes_within(data, max_size)?),
+        header::Compression::Store => Zeroizing::new(data.to_vec()),
+        header::Compression::SmallText => Zeroizing::new(unpack_ascii(data)),
+        header::Compression::Dictionary => match data {
+            [PRESET, data @ ..] => Dictionary::preset().decompress(data, max_size)?,
+            [id, data @ ..] => match custom {
+                Some(dictionary) if dictionary.id() == *id => {
+                    dictionary.decompress(data, max_size)?
+                }
+                _ => return Err(DeCompressError::UnknownDictionary(*id)),
+            },
+            [] => return Err(DeCompressError::Malformed),
+        },
+    };
+
+    if data.len() > max_size {
+        return Err(DeCompressError::TooLarge(max_size));
+    }
 
     Ok(data)
 }
 
+/// Pack ASCII text into 7 bits per char, LSB first, or `None` if it isn't ASCII or has NULs
+fn pack_ascii(data: &[u8]) -> Option<Vec<u8>> {
+    if data.iter().any(|&b| b == 0 || !b.is_ascii()) {
+        return None;
+    }
+
+    let mut packed = Vec::with_capacity((data.len() * 7).div_ceil(8));
+    let (mut acc, mut bits) = (0u16, 0);
+    for &b in data {
+        acc |= u16::from(b) << bits;
+        bits += 7;
+
+        while bits >= 8 {
+            packed.push(acc as u8);
+            acc >>= 8;
+            bits -= 8;
+        }
+    }
+
+    if bits > 0 {
+        packed.push(acc as u8);
+    }
+
+    Some(packed)
+}
+
+fn unpack_ascii(data: &[u8]) -> Vec<u8> {
+    let mut text = Vec::with_capacity(data.len() * 8 / 7);
+    let (mut acc, mut bits) = (0u16, 0);
+    for &b in data {
+        acc |= u16::from(b) << bits;
+        bits += 8;
+
+        while bits >= 7 {
+            text.push((acc & 0x7f) as u8);
+            acc >>= 7;
+            bits -= 7;
+        }
+    }
+
+    // the padding of the last byte holds a whole char of 0 bits, which packed text never has
+    if text.last() == Some(&0) {
+        text.pop();
+    }
+
+    text
+}
+
+/// Compress a string using deflate
+pub fn compress(data: &str) -> Result<Vec<u8>, DeCompressError> {
+    compress_bytes(data.as_bytes())
+}
+
 /// Decompress deflated stream back into string
 pub fn decompress(data: &[u8]) -> Result<String, DeCompressError> {
-    let mut decoder = DeflateDecoder::new(Vec::new());
+    to_string(decompress_bytes(data)?)
+}
 
-    decoder.write_all(data)?;
-    let data = decoder.finish()?;
+/// Bytes as a string, which are zeroized if they aren't utf-8
+pub(crate) fn to_string(data: Vec<u8>) -> Result<String, DeCompressError> {
+    String::from_utf8(data).map_err(|e| {
+        // the error holds on to the secret, which mustn't outlive it
+        e.into_bytes().zeroize();
+        DeCompressError::InvalidUtf8
+    })
+}
+
+/// Compress binary data using deflate
+pub fn compress_bytes(data: &[u8]) -> Result<Vec<u8>, DeCompressError> {
+    compress_bytes_with(data, Compression::best().level())
+}
+
+/// Compress binary data using deflate at a specific level
+///
+/// Levels range from 0 (store only) to 9 (best), anything higher is treated as 9
+pub fn compress_bytes_with(data: &[u8], level: u32) -> Result<Vec<u8>, DeCompressError> {
+    let level = Compression::new(level.min(Compression::best().level()));
+
+    let mut encoder = DeflateEncoder::new(Buffer::new(usize::MAX), level);
+    encoder.write_all(data)?;
 
-    Ok(String::from_utf8(data)?)
+    Ok(encoder.finish()?.into_vec())
+}
+
+/// Decompress deflated stream back into binary data, up to [`MAX_SIZE`] bytes
+pub fn decompress_bytes(data: &[u8]) -> Result<Vec<u8>, DeCompressError> {
+    decompress_bytes_within(data, MAX_SIZE)
+}
+
+/// Decompress deflated stream back into binary data, failing once it exceeds `max_size` bytes
+pub fn decompress_bytes_within(data: &[u8], max_size: usize) -> Result<Vec<u8>, DeCompressError> {
+    let mut decoder = DeflateDecoder::new(Buffer::new(max_size));
+
+    let result = decoder.write_all(data).and_then(|()| decoder.try_finish());
+    if decoder.get_ref().exceeded {
+        return Err(DeCompressError::TooLarge(max_size));
+    }
+    result?;
+
+    Ok(decoder.finish()?.into_vec())
+}
+
+/// Buffer for plaintext, which refuses writes past `max_size` bytes
+///
+/// Growing a `Vec` leaves a copy behind in the allocation it moved out of, so this wipes the
+/// old allocation on every move, and the last one when dropped.
+struct Buffer {
+    data: Zeroizing<Vec<u8>>,
+    max_size: usize,
+    exceeded: bool,
+}
+
+impl Buffer {
+    fn new(max_size: usize) -> Self {
+        Self {
+            data: Zeroizing::new(Vec::new()),
+            max_size,
+            exceeded: false,
+        }
+    }
+
+    /// The data, no longer wiped once dropped
+    fn into_vec(mut self) -> Vec<u8> {
+        mem::take(&mut *self.data)
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.data.len() + buf.len() > self.max_size {
+            self.exceeded = true;
+            return Err(io::Error::other("decompressed data is too large"));
+        }
+
+        if self.data.capacity() - self.data.len() < buf.len() {
+            let capacity = (self.data.len() + buf.len()).max(self.data.capacity() * 2);
+            let mut grown = Vec::with_capacity(capacity);
+            grown.extend_from_slice(&self.data);
+
+            // the old allocation is wiped as it drops
+            self.data = Zeroizing::new(grown);
+        }
+
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writer which deflates everything written to it into `inner`, at a specific level
+///
+/// Call `finish` on it when done
+pub fn compress_writer<W: Write>(inner: W, level: u32) -> DeflateEncoder<W> {
+    DeflateEncoder::new(
+        inner,
+        Compression::new(level.min(Compression::best().level())),
+    )
+}
+
+/// Reader which inflates the deflated stream read from `inner`
+pub fn decompress_reader<R: Read>(inner: R) -> read::DeflateDecoder<R> {
+    read::DeflateDecoder::new(inner)
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum DeCompressError {
-    #[error("Failed to decode string: {0}")]
-    StringError(#[from] FromUtf8Error),
+    #[error("Secret isn't valid utf-8")]
+    InvalidUtf8,
     #[error("IoError: {0:?}")]
     IoError(#[from] io::Error),
+    #[error("Compressed data is malformed")]
+    Malformed,
+    #[error("Decompressed data is larger than the limit of {0} bytes")]
+    TooLarge(usize),
+    #[error("Secret was compressed with dictionary {0}, which wasn't given")]
+    UnknownDictionary(u8),
+    #[error("Dictionaries need an id other than the preset's and up to 254 non-empty entries")]
+    InvalidDictionary,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii() {
+        for len in 0..=17 {
+            let text = &b"abcdefghijklmnopq"[..len];
+            let packed = pack_ascii(text).unwrap();
+
+            assert_eq!(packed.len(), (len * 7).div_ceil(8));
+            assert_eq!(unpack_ascii(&packed), text);
+        }
+
+        assert_eq!(pack_ascii("caf\u{e9}".as_bytes()), None);
+        assert_eq!(pack_ascii(b"a\0b"), None);
+    }
+
+    #[test]
+    fn test_smallest() {
+        let compressors = [
+            Compressor::Deflate { level: 9 },
+            Compressor::SmallText,
+            Compressor::Store,
+        ];
+
+        let cases: [(&[u8], header::Compression); 4] = [
+            (b"mysecret", header::Compression::SmallText),
+            (&[0xff, 0, 0xfe], header::Compression::Store),
+            (&[0; 100], header::Compression::Deflate),
+            (b"", header::Compression::SmallText),
+        ];
+
+        for (data, compression) in cases {
+            let (chosen, compressed) = compress_smallest(data, &compressors);
+            assert_eq!(chosen, compression, "{data:?}");
+            assert_eq!(
+                *decompress_as(&compressed, chosen, None, MAX_SIZE).unwrap(),
+                data
+            );
+        }
+
+        let (chosen, _) = compress_smallest(b"\0", &[Compressor::SmallText]);
+        assert_eq!(chosen, header::Compression::Store);
+    }
+
+    #[test]
+    fn test_buffer() {
+        let mut buffer = Buffer::new(10);
+        buffer.write_all(&[1; 3]).unwrap();
+        buffer.write_all(&[2; 7]).unwrap();
+        assert!(buffer.write_all(&[3]).is_err());
+        assert!(buffer.exceeded);
+
+        let mut data = [1; 3].to_vec();
+        data.extend([2; 7]);
+        assert_eq!(buffer.into_vec(), data);
+    }
+
+    #[test]
+    fn test_too_large() {
+        let data = vec![0; 10_000];
+        let compressed = compress_bytes(&data).unwrap();
+
+        assert_eq!(decompress_bytes_within(&compressed, 10_000).unwrap(), data);
+        assert!(matches!(
+            decompress_bytes_within(&compressed, 9_999),
+            Err(DeCompressError::TooLarge(9_999))
+        ));
+
+        let dictionary = Dictionary::new(1, [vec![0; 1000]]).unwrap();
+        let compressed = dictionary.compress(&data[..4_000]).unwrap();
+        let chosen = header::Compression::Dictionary;
+        assert!(matches!(
+            decompress_as(&compressed, chosen, Some(&dictionary), 3_000),
+            Err(DeCompressError::TooLarge(3_000))
+        ));
+
+        let chosen = header::Compression::Store;
+        assert!(matches!(
+            decompress_as(&data, chosen, None, 5_000),
+            Err(DeCompressError::TooLarge(5_000))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let compressed = compress_bytes(b"secret \xff").unwrap();
+        let error = decompress(&compressed).unwrap_err();
+
+        assert!(matches!(error, DeCompressError::InvalidUtf8));
+        assert!(!format!("{error} {error:?}").contains("115"));
+    }
+
+    #[test]
+    fn test_dictionary() {
+        let compressors = [
+            Compressor::Deflate { level: 9 },
+            Compressor::SmallText,
+            Compressor::Dictionary(Dictionary::preset()),
+        ];
+
+        let secret = b"meet me at the station";
+        let (chosen, compressed) = compress_smallest(secret, &compressors);
+        assert_eq!(chosen, header::Compression::Dictionary);
+        assert_eq!(
+            *decompress_as(&compressed, chosen, None, MAX_SIZE).unwrap(),
+            secret
+        );
+
+        let custom = Dictionary::new(3, ["correct horse battery staple"]).unwrap();
+        let compressed = custom.compress(b"correct horse battery staple").unwrap();
+        assert_eq!(compressed.len(), 2);
+
+        let chosen = header::Compression::Dictionary;
+        assert_eq!(
+            *decompress_as(&compressed, chosen, Some(&custom), MAX_SIZE).unwrap(),
+            b"correct horse battery staple"
+        );
+        assert!(matches!(
+            decompress_as(&compressed, chosen, None, MAX_SIZE),
+            Err(DeCompressError::UnknownDictionary(3))
+        ));
+    }
 }
@@ -0,0 +1,245 @@
+//! SMAZ-style codebook compression for short secrets
+//!
+//! Every byte of the output is either the index of a codebook entry, or an escape for bytes
+//! which no entry matched: [`LITERAL`] before a single byte, [`LITERALS`] before a length byte
+//! and a run of up to 256 bytes.
+
+use std::io::Write as _;
+
+use zeroize::Zeroizing;
+
+use super::{Buffer, DeCompressError};
+
+/// Escape for a single byte kept as is
+const LITERAL: u8 = 254;
+
+/// Escape for a run of bytes kept as is, followed by its length minus one
+const LITERALS: u8 = 255;
+
+/// Most entries a codebook can have, every byte below the escapes
+pub const MAX_ENTRIES: usize = LITERAL as usize;
+
+/// Longest secret worth matching against a codebook, longer ones compress better with deflate
+pub const MAX_INPUT: usize = 4096;
+
+/// Id of the preset codebook, custom dictionaries take any other
+pub const PRESET: u8 = 0;
+
+/// Fragments of English text and URLs, roughly the most common first
+const PRESET_ENTRIES: &[&str] = &[
+    " ", "the", "e", "t", "a", "of", "o", "and", "i", "n", "s", "e ", "r", " th", " t", "in", "he",
+    "th", "h", "he ", "to", "\r\n", "l", "s ", "d", " a", "an", "er", "c", " o", "d ", "on", " of",
+    "re", "of ", "t ", ", ", "is", "u", "at", "   ", "n ", "or", "which", "f", "m", "as", "it",
+    "that", "\n", "was", "en", "  ", " w", "es", " an", " i", "\r", "f ", "g", "p", "nd", " s",
+    "nd ", "ed ", "w", "ed", "http://", "https://", "www.", ".com", ".org", ".net", ".io", "for",
+    "te", "ing", "y ", "The", " c", "ti", "r ", "his", "st", " in", "ar", "nt", ",", " to", "y",
+    "ng", " h", "with", "le", "al", "to ", "b", "ou", "be", "were", " b", "se", "o ", "ent", "ha",
+    "ng ", "their", "\"", "hi", "from", " f", "in ", "de", "ion", "me", "v", ".", "ve", "all",
+    "re ", "ri", "ro", "is ", "co", "f t", "are", "ea", ". ", "her", " m", "er ", " p", "es ",
+    "by", "they", "di", "ra", "ic", "not", "s, ", "d t", "at ", "ce", "la", "h ", "ne", "as ",
+    "tio", "on ", "n t", "io", "we", " a ", "om", ", a", "s o", "ur", "li", "ll", "ch", "had",
+    "this", "e t", "g ", "e\r\n", " wh", "ere", " co", "e o", "a ", "us", " d", "ss", " be", " e",
+    "s a", "ma", "one", "t t", "or ", "but", "el", "so", "l ", "e s", "s,", "no", "ter", " wa",
+    "iv", "ho", "e a", " r", "hat", "s t", "ns", "ch ", "wh", "tr", "ut", "/", "have", "ly ", "ta",
+    " ha", " on", "tha", "-", " l", "ati", "en ", "pe", " re", "there", "ass", "si", " fo", "wa",
+    "ec", "our", "who", "its", "z", "fo", "rs", ">", "ot", "un", "<", "im", "th ", "nc", "ate",
+    "ver", "ad", " we", "ly", "ee", " n", "id", " cl", "ac", "il", "rt", " wi", "e, ", " it",
+    "whi", " ma", "ge", "x", "e c", "men", ".com/",
+];
+
+const _: () = assert!(PRESET_ENTRIES.len() <= MAX_ENTRIES);
+
+/// A codebook of byte strings which compress to a single byte each
+///
+/// Custom dictionaries tuned to the secrets at hand are recorded by their id, so revealing
+/// needs the same dictionary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dictionary {
+    id: u8,
+    entries: Vec<Vec<u8>>,
+}
+
+impl Dictionary {
+    /// Create a custom dictionary
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Recorded with every secret compressed with it, anything but [`PRESET`]
+    /// * `entries` - Up to [`MAX_ENTRIES`] non-empty byte strings, the most common first
+    pub fn new(
+        id: u8,
+        entries: impl IntoIterator<Item = impl Into<Vec<u8>>>,
+    ) -> Result<Self, DeCompressError> {
+        let entries = entries.into_iter().map(Into::into).collect::<Vec<_>>();
+
+        if id == PRESET || entries.len() > MAX_ENTRIES || entries.iter().any(Vec::is_empty) {
+            return Err(DeCompressError::InvalidDictionary);
+        }
+
+        Ok(Self { id, entries })
+    }
+
+    /// The built-in dictionary for short English text and URLs
+    pub fn preset() -> Self {
+        Self {
+            id: PRESET,
+            entries: PRESET_ENTRIES
+                .iter()
+                .map(|e| e.as_bytes().to_vec())
+                .collect(),
+        }
+    }
+
+    /// Id recorded with every secret compressed with this dictionary
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Compress data into codebook indices and escaped literals, after the dictionary id
+    ///
+    /// `None` for data longer than [`MAX_INPUT`].
+    pub fn compress(&self, data: &[u8]) -> Option<Vec<u8>> {
+        if data.len() > MAX_INPUT {
+            return None;
+        }
+
+        let mut out = vec![self.id];
+        let mut literals = Vec::new();
+
+        let mut rest = data;
+        while let Some(&b) = rest.first() {
+            // longest entry wins, the earlier one on ties
+            let matched = self
+                .entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| rest.starts_with(entry))
+                .max_by(|(i, a), (j, b)| a.len().cmp(&b.len()).then(j.cmp(i)));
+
+            match matched {
+                Some((index, entry)) => {
+                    flush(&mut literals, &mut out);
+                    out.push(index as u8);
+                    rest = &rest[entry.len()..];
+                }
+                None => {
+                    literals.push(b);
+                    if literals.len() == 256 {
+                        flush(&mut literals, &mut out);
+                    }
+                    rest = &rest[1..];
+                }
+            }
+        }
+
+        flush(&mut literals, &mut out);
+        Some(out)
+    }
+
+    /// Decompress data compressed with [`Dictionary::compress`], without the dictionary id
+    ///
+    /// Entries can be long, so this stops once the output exceeds `max_size` bytes.
+    pub(super) fn decompress(
+        &self,
+        data: &[u8],
+        max_size: usize,
+    ) -> Result<Zeroizing<Vec<u8>>, DeCompressError> {
+        let mut out = Buffer::new(max_size);
+
+        let mut rest = data;
+        while let [code, tail @ ..] = rest {
+            let (bytes, tail) = match (*code, tail) {
+                (LITERAL, [b, tail @ ..]) => (std::slice::from_ref(b), tail),
+                (LITERALS, [len, tail @ ..]) => tail
+                    .split_at_checked(*len as usize + 1)
+                    .ok_or(DeCompressError::Malformed)?,
+                (LITERAL | LITERALS, []) => return Err(DeCompressError::Malformed),
+                (index, tail) => {
+                    let entry = self
+                        .entries
+                        .get(index as usize)
+                        .ok_or(DeCompressError::Malformed)?;
+                    (&entry[..], tail)
+                }
+            };
+
+            out.write_all(bytes)
+                .map_err(|_| DeCompressError::TooLarge(max_size))?;
+            rest = tail;
+        }
+
+        Ok(Zeroizing::new(out.into_vec()))
+    }
+}
+
+/// Write pending literals escaped to `out`
+fn flush(literals: &mut Vec<u8>, out: &mut Vec<u8>) {
+    match literals.len() {
+        0 => return,
+        1 => out.push(LITERAL),
+        len => out.extend([LITERALS, (len - 1) as u8]),
+    }
+
+    out.append(literals);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let dictionary = Dictionary::preset();
+
+        let long = [0xff; 300];
+        let cases: [&[u8]; 6] = [
+            b"",
+            b"meet me at the station at noon",
+            b"https://www.example.com/path?q=1",
+            "caf\u{e9} \u{1f980}".as_bytes(),
+            &[LITERAL, LITERALS, 0],
+            &long,
+        ];
+
+        for data in cases {
+            let compressed = dictionary.compress(data).unwrap();
+            assert_eq!(compressed[0], PRESET);
+            assert_eq!(
+                *dictionary.decompress(&compressed[1..], usize::MAX).unwrap(),
+                data
+            );
+        }
+
+        let compressed = dictionary.compress(b"meet me at the station").unwrap();
+        assert!(compressed.len() < "meet me at the station".len() * 2 / 3);
+
+        assert_eq!(dictionary.compress(&[b'a'; MAX_INPUT + 1]), None);
+    }
+
+    #[test]
+    fn test_custom() {
+        let dictionary = Dictionary::new(7, ["hunter2", "password"]).unwrap();
+        assert_eq!(dictionary.compress(b"password").unwrap(), [7, 1]);
+        assert_eq!(
+            *dictionary.decompress(&[1, 0], usize::MAX).unwrap(),
+            b"passwordhunter2"
+        );
+
+        assert!(matches!(
+            Dictionary::new(PRESET, ["a"]),
+            Err(DeCompressError::InvalidDictionary)
+        ));
+        assert!(matches!(
+            Dictionary::new(1, [""]),
+            Err(DeCompressError::InvalidDictionary)
+        ));
+        assert!(matches!(
+            dictionary.decompress(&[2], usize::MAX),
+            Err(DeCompressError::Malformed)
+        ));
+        assert!(matches!(
+            dictionary.decompress(&[LITERALS, 5, b'a'], usize::MAX),
+            Err(DeCompressError::Malformed)
+        ));
+    }
+}
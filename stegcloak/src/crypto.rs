@@ -1,23 +1,222 @@
+pub mod recipient;
+pub mod signature;
+pub mod stream;
+
 use std::str::Utf8Error;
 
 use aes::{
     cipher::{generic_array::GenericArray, InvalidLength, KeyIvInit as _, StreamCipher},
     Aes256,
 };
+use aes_gcm::{aead::Aead, Aes256Gcm};
+use argon2::{Algorithm, Argon2, Version};
 use bincode::{
     error::{DecodeError, EncodeError},
     Decode, Encode,
 };
+use hkdf::Hkdf;
 use hmac::{Hmac, Mac};
-use pbkdf2::{password_hash::SaltString, pbkdf2_hmac_array};
+use pbkdf2::password_hash::SaltString;
 use rand::rngs::OsRng;
 use sha2::{Sha256, Sha512};
+use zeroize::Zeroizing;
+
+use chacha20poly1305::ChaCha20Poly1305;
 
 type Aes256Ctr = ctr::Ctr128LE<Aes256>;
 type HmacSha256 = Hmac<Sha256>;
 
+/// Default number of PBKDF2 rounds
+pub const PBKDF2_ROUNDS: u32 = 10_000;
+/// Upper bound on PBKDF2 rounds, so a crafted payload can't stall decryption
+pub const MAX_PBKDF2_ROUNDS: u32 = 10_000_000;
+/// Upper bound on Argon2 memory cost in KiB (256 MiB), so a crafted payload can't exhaust memory
+///
+/// The cost comes from the payload, which may be untrusted, and wasm builds can't spare much more.
+pub const MAX_ARGON2_M_COST: u32 = 256 * 1024;
+/// Upper bound on Argon2 iterations, so a crafted payload can't stall decryption
+pub const MAX_ARGON2_T_COST: u32 = 64;
+/// Upper bound on Argon2 parallelism
+pub const MAX_ARGON2_P_COST: u32 = 16;
+
+/// Key derivation function used to turn a password into key material
+///
+/// The chosen kdf and its parameters are stored in the payload, so changing the
+/// defaults never breaks previously encrypted data.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum Kdf {
+    /// PBKDF2-HMAC-SHA512 with the given number of rounds
+    Pbkdf2 { rounds: u32 },
+    /// Argon2id with memory cost in KiB, number of iterations and degree of parallelism
+    Argon2id {
+        m_cost: u32,
+        t_cost: u32,
+        p_cost: u32,
+    },
+}
+
+impl Default for Kdf {
+    fn default() -> Self {
+        Self::Pbkdf2 {
+            rounds: PBKDF2_ROUNDS,
+        }
+    }
+}
+
+impl Kdf {
+    /// Argon2id with the recommended default parameters
+    pub fn argon2id() -> Self {
+        Self::Argon2id {
+            m_cost: argon2::Params::DEFAULT_M_COST,
+            t_cost: argon2::Params::DEFAULT_T_COST,
+            p_cost: argon2::Params::DEFAULT_P_COST,
+        }
+    }
+
+    /// Derive 48 bytes of iv + key material
+    fn derive(&self, password: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 48]>, DeEncryptError> {
+        match *self {
+            Kdf::Pbkdf2 { rounds } => {
+                if rounds == 0 || rounds > MAX_PBKDF2_ROUNDS {
+                    return Err(DeEncryptError::InvalidKdfParams);
+                }
+
+                let mut iv_key = Zeroizing::new([0u8; 48]);
+                pbkdf2::pbkdf2_hmac::<Sha512>(password.as_bytes(), salt, rounds, &mut *iv_key);
+
+                Ok(iv_key)
+            }
+
+            Kdf::Argon2id {
+                m_cost,
+                t_cost,
+                p_cost,
+            } => {
+                if m_cost > MAX_ARGON2_M_COST
+                    || t_cost > MAX_ARGON2_T_COST
+                    || p_cost > MAX_ARGON2_P_COST
+                {
+                    return Err(DeEncryptError::InvalidKdfParams);
+                }
+
+                let params = argon2::Params::new(m_cost, t_cost, p_cost, Some(48))
+                    .map_err(|_| DeEncryptError::InvalidKdfParams)?;
+
+                let mut iv_key = Zeroizing::new([0u8; 48]);
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(password.as_bytes(), salt, &mut *iv_key)
+                    .map_err(|_| DeEncryptError::InvalidKdfParams)?;
+
+                Ok(iv_key)
+            }
+        }
+    }
+}
+
+/// Cipher used to encrypt the data
+///
+/// The chosen cipher is stored in the payload, so decryption always uses the right one.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Encode, Decode)]
+pub enum Cipher {
+    /// AES-256-CTR, only authenticated (with HMAC-SHA256) when integrity is requested
+    #[default]
+    Aes256Ctr,
+    /// AES-256-GCM, always authenticated
+    Aes256Gcm,
+    /// ChaCha20-Poly1305, always authenticated
+    ChaCha20Poly1305,
+}
+
+impl Cipher {
+    /// Whether this is an authenticated cipher which always detects tampering
+    pub fn is_aead(&self) -> bool {
+        !matches!(self, Cipher::Aes256Ctr)
+    }
+}
+
+/// Encryption parameters
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Params {
+    /// Create payload that protects against tampering
+    ///
+    /// Note: Aead ciphers always protect against tampering
+    pub integrity: bool,
+    /// Key derivation function
+    pub kdf: Kdf,
+    /// Cipher used to encrypt the data
+    pub cipher: Cipher,
+}
+
+/// Key derivation of version 1 payloads, which were always encrypted with AES-256-CTR
+const LEGACY_KDF: Kdf = Kdf::Pbkdf2 { rounds: 10_000 };
+
+/// Current payload format version, see [`PayloadVersion::Keyed`]
+const PAYLOAD_VERSION: u8 = 2;
+
+/// Payload layouts, told apart by their first byte
+///
+/// Every change to the layout of a payload gets a new version, so payloads written by any
+/// earlier release keep decrypting.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PayloadVersion {
+    /// Version 1, which starts with its integrity flag instead of a version and stores a full
+    /// hmac of the password
+    Unversioned,
+    /// Version 2, which adds the kdf and cipher and replaces the password hmac with a key check
+    Keyed,
+    /// Version 3, streamed AES-256-CTR, see [`stream`]
+    Streamed,
+}
+
+impl PayloadVersion {
+    fn of(data: &[u8]) -> Result<Self, DeEncryptError> {
+        match data.first() {
+            Some(0 | 1) => Ok(PayloadVersion::Unversioned),
+            Some(&PAYLOAD_VERSION) => Ok(PayloadVersion::Keyed),
+            Some(&stream::PAYLOAD_VERSION) => Ok(PayloadVersion::Streamed),
+            Some(&version) => Err(DeEncryptError::UnsupportedPayloadVersion(version)),
+            None => Err(DecodeError::UnexpectedEnd { additional: 1 }.into()),
+        }
+    }
+}
+
+/// Length of the key check value
+///
+/// It's just long enough to tell a wrong password apart from corrupted data. A 16 bit check
+/// lets through 1 in 65536 wrong passwords, so on its own it can't confirm a password guess.
+const KEY_CHECK_LEN: usize = 2;
+
+/// Length of the tag aead ciphers append to the ciphertext
+const AEAD_TAG_LEN: usize = 16;
+
 #[derive(Debug, Encode, Decode)]
 struct Payload {
+    version: u8,
+    kdf: Kdf,
+    cipher: Cipher,
+    salt: [u8; 22],
+    key_check: [u8; KEY_CHECK_LEN],
+    hmac: Option<[u8; 32]>,
+    data: Vec<u8>,
+}
+
+/// How a payload was encrypted, as the payload itself says
+///
+/// It's bound into the derived keys, so swapping an aead cipher for AES-256-CTR or stripping
+/// the hmac makes the key check fail instead of decrypting with weaker protection.
+#[derive(Debug, Encode)]
+struct Suite {
+    version: u8,
+    kdf: Kdf,
+    cipher: Cipher,
+    integrity: bool,
+}
+
+/// Version 1 payload, as written by 0.1.6 and before
+///
+/// Its kdf and cipher are fixed, see [`LEGACY_KDF`].
+#[derive(Debug, Decode)]
+struct LegacyPayload {
     integrity: bool,
     password_hmac: [u8; 32],
     salt: [u8; 22],
@@ -30,6 +229,29 @@ struct Payload {
 /// Requirements:
 /// - password.len > 0
 pub fn encrypt(password: &str, data: &[u8], integrity: bool) -> Result<Vec<u8>, DeEncryptError> {
+    let params = Params {
+        integrity,
+        ..Default::default()
+    };
+
+    encrypt_with(password, data, &params)
+}
+
+/// Encrypt a binary stream using custom parameters
+///
+/// Requirements:
+/// - password.len > 0
+pub fn encrypt_with(
+    password: &str,
+    data: &[u8],
+    params: &Params,
+) -> Result<Vec<u8>, DeEncryptError> {
+    let Params {
+        integrity,
+        kdf,
+        cipher,
+    } = *params;
+
     if password.is_empty() {
         return Err(DeEncryptError::PasswordTooShort);
     }
@@ -37,31 +259,25 @@ pub fn encrypt(password: &str, data: &[u8], integrity: bool) -> Result<Vec<u8>,
     // 🧂🍳 22 salty bytes
     let salt = SaltString::generate(&mut OsRng);
 
-    // generate key
-    let iv_key =
-        pbkdf2_hmac_array::<Sha512, 48>(password.as_bytes(), salt.as_str().as_bytes(), 10_000);
-    let iv = GenericArray::from_slice(&iv_key[..16]);
-    let key = GenericArray::from_slice(&iv_key[16..]);
-
-    let mut data = data.to_vec();
-    let mut cipher = Aes256Ctr::new(key, iv);
-    cipher.apply_keystream(&mut data);
-
-    let mut password_hmac = HmacSha256::new_from_slice(key)?;
-    password_hmac.update(password.as_bytes());
-    let password_hmac = password_hmac.finalize().into_bytes().into();
+    // aead ciphers never get an hmac on top
+    let integrity = integrity && !cipher.is_aead();
+    let suite = Suite {
+        version: PAYLOAD_VERSION,
+        kdf,
+        cipher,
+        integrity,
+    };
 
-    let mut hmac = None;
-    if integrity {
-        let mut _hmac = HmacSha256::new_from_slice(key)?;
-        _hmac.update(&data);
-        hmac = Some(_hmac.finalize().into_bytes().into());
-    }
+    // generate key
+    let (iv_key, key_check) = derive_keys(&suite, password, salt.as_str().as_bytes())?;
+    let (data, hmac) = seal(cipher, integrity, &iv_key, data)?;
 
     let payload = Payload {
-        integrity,
-        password_hmac,
+        version: PAYLOAD_VERSION,
+        kdf,
+        cipher,
         salt: salt.to_string().as_bytes().try_into().unwrap(),
+        key_check,
         hmac,
         data,
     };
@@ -71,6 +287,31 @@ pub fn encrypt(password: &str, data: &[u8], integrity: bool) -> Result<Vec<u8>,
     Ok(payload)
 }
 
+/// Length of the payload [`encrypt_with`] returns for `len` bytes of data
+///
+/// It only depends on the parameters, so it's known without deriving any keys.
+pub fn encrypted_len(params: &Params, len: usize) -> usize {
+    let tag = if params.cipher.is_aead() {
+        AEAD_TAG_LEN
+    } else {
+        0
+    };
+
+    let payload = Payload {
+        version: PAYLOAD_VERSION,
+        kdf: params.kdf,
+        cipher: params.cipher,
+        salt: [0; 22],
+        key_check: [0; KEY_CHECK_LEN],
+        hmac: (params.integrity && !params.cipher.is_aead()).then_some([0; 32]),
+        data: vec![0; len + tag],
+    };
+
+    bincode::encode_to_vec(payload, bincode::config::standard())
+        .expect("payloads always encode")
+        .len()
+}
+
 /// Decrypt binary stream
 ///
 /// Requirements:
@@ -81,43 +322,206 @@ pub fn encrypt(password: &str, data: &[u8], integrity: bool) -> Result<Vec<u8>,
 ///
 /// Note: It is possible in rare cases decrypted data might pass successfully (particularly when not using
 ///       the integrity check). And in such a case the resulting returned data _may_ be corrupt.
-pub fn decrypt(password: &str, data: &[u8]) -> Result<Vec<u8>, DeEncryptError> {
+///       Data encrypted with an aead [`Cipher`] always fails with [`DeEncryptError::IntegrityError`] instead.
+pub fn decrypt(password: &str, data: &[u8]) -> Result<Zeroizing<Vec<u8>>, DeEncryptError> {
     if password.is_empty() {
         return Err(DeEncryptError::PasswordTooShort);
     }
 
-    let (mut payload, _) =
-        bincode::decode_from_slice::<Payload, _>(data, bincode::config::standard())?;
+    match PayloadVersion::of(data)? {
+        PayloadVersion::Unversioned => return decrypt_legacy(password, data),
+        PayloadVersion::Streamed => return stream::decrypt(password, data),
+        PayloadVersion::Keyed => (),
+    }
+
+    let (payload, _) = bincode::decode_from_slice::<Payload, _>(data, bincode::config::standard())?;
+
+    let integrity = payload.hmac.is_some();
+    if integrity && payload.cipher.is_aead() {
+        return Err(DeEncryptError::IntegrityError);
+    }
 
     // Extract salt
     let salt = SaltString::from_b64(std::str::from_utf8(&payload.salt)?)?;
 
     // Generate key
-    let iv_key =
-        pbkdf2_hmac_array::<Sha512, 48>(password.as_bytes(), salt.as_str().as_bytes(), 10_000);
-    let iv = GenericArray::from_slice(&iv_key[..16]);
-    let key = GenericArray::from_slice(&iv_key[16..]);
+    let suite = Suite {
+        version: payload.version,
+        kdf: payload.kdf,
+        cipher: payload.cipher,
+        integrity,
+    };
+    let (iv_key, key_check) = derive_keys(&suite, password, salt.as_str().as_bytes())?;
+
+    if key_check != payload.key_check {
+        return Err(DeEncryptError::IncorrectPassword);
+    }
+
+    open(
+        payload.cipher,
+        integrity,
+        payload.hmac,
+        &iv_key,
+        payload.data,
+    )
+}
+
+/// Cipher of a payload and whether it carries an hmac, as the payload says before anything is
+/// verified
+///
+/// Lets callers refuse a payload which isn't protected the way they expect, like the integrity
+/// of a [`crate::header::Header`] says.
+pub fn suite(data: &[u8]) -> Result<(Cipher, bool), DeEncryptError> {
+    let config = bincode::config::standard();
+
+    match PayloadVersion::of(data)? {
+        PayloadVersion::Unversioned => {
+            let (payload, _) = bincode::decode_from_slice::<LegacyPayload, _>(data, config)?;
+            Ok((Cipher::Aes256Ctr, payload.integrity))
+        }
+        PayloadVersion::Keyed => {
+            let (payload, _) = bincode::decode_from_slice::<Payload, _>(data, config)?;
+            Ok((payload.cipher, payload.hmac.is_some()))
+        }
+        PayloadVersion::Streamed => Ok((Cipher::Aes256Ctr, stream::integrity(data)?)),
+    }
+}
+
+/// Decrypt a version 1 payload
+fn decrypt_legacy(password: &str, data: &[u8]) -> Result<Zeroizing<Vec<u8>>, DeEncryptError> {
+    let (payload, _) =
+        bincode::decode_from_slice::<LegacyPayload, _>(data, bincode::config::standard())?;
+
+    // Extract salt
+    let salt = SaltString::from_b64(std::str::from_utf8(&payload.salt)?)?;
+
+    // Generate key
+    let iv_key = LEGACY_KDF.derive(password, salt.as_str().as_bytes())?;
 
     // verify password integrity
-    let mut pwd_hmac = HmacSha256::new_from_slice(key)?;
+    let mut pwd_hmac = HmacSha256::new_from_slice(&iv_key[16..])?;
     pwd_hmac.update(password.as_bytes());
     pwd_hmac
         .verify_slice(&payload.password_hmac)
         .map_err(|_| DeEncryptError::IncorrectPassword)?;
 
-    if payload.integrity {
-        // Verify hmac
-        let mut mac = HmacSha256::new_from_slice(key)?;
-        mac.update(&payload.data);
-        mac.verify_slice(&payload.hmac.unwrap())
-            .map_err(|_| DeEncryptError::IntegrityError)?;
-    }
+    open(
+        Cipher::Aes256Ctr,
+        payload.integrity,
+        payload.hmac,
+        &iv_key,
+        payload.data,
+    )
+}
 
-    // Decrypt payload
-    let mut cipher = Aes256Ctr::new(key, iv);
-    cipher.apply_keystream(&mut payload.data);
+/// Derive iv + key material and the key check value from a password, for the payload's `suite`
+///
+/// Both are expanded from the kdf output separately, so the key check value reveals nothing
+/// about the key itself
+fn derive_keys(
+    suite: &Suite,
+    password: &str,
+    salt: &[u8],
+) -> Result<(Zeroizing<[u8; 48]>, [u8; KEY_CHECK_LEN]), DeEncryptError> {
+    let okm = suite.kdf.derive(password, salt)?;
+    let hkdf = Hkdf::<Sha256>::new(Some(salt), okm.as_slice());
+    let suite = bincode::encode_to_vec(suite, bincode::config::standard())?;
+
+    let mut iv_key = Zeroizing::new([0u8; 48]);
+    hkdf.expand_multi_info(&[b"stegcloak iv key", &suite], &mut *iv_key)
+        .map_err(|_| DeEncryptError::InvalidKdfParams)?;
+
+    let mut key_check = [0u8; KEY_CHECK_LEN];
+    hkdf.expand_multi_info(&[b"stegcloak key check", &suite], &mut key_check)
+        .map_err(|_| DeEncryptError::InvalidKdfParams)?;
+
+    Ok((iv_key, key_check))
+}
+
+/// Encrypt data with a cipher, returning the ciphertext and hmac if one was requested
+fn seal(
+    cipher: Cipher,
+    integrity: bool,
+    iv_key: &[u8; 48],
+    data: &[u8],
+) -> Result<(Vec<u8>, Option<[u8; 32]>), DeEncryptError> {
+    let iv = GenericArray::from_slice(&iv_key[..16]);
+    let key = GenericArray::from_slice(&iv_key[16..]);
+
+    // aead nonces are unique since the key is derived from a fresh salt every time
+    let nonce = GenericArray::from_slice(&iv_key[..12]);
+
+    let mut hmac = None;
+    let data = match cipher {
+        Cipher::Aes256Ctr => {
+            let mut data = data.to_vec();
+            let mut cipher = Aes256Ctr::new(key, iv);
+            cipher.apply_keystream(&mut data);
+
+            if integrity {
+                let mut _hmac = HmacSha256::new_from_slice(key)?;
+                _hmac.update(&data);
+                hmac = Some(_hmac.finalize().into_bytes().into());
+            }
+
+            data
+        }
+
+        Cipher::Aes256Gcm => <Aes256Gcm as aes_gcm::KeyInit>::new(key)
+            .encrypt(nonce, data)
+            .map_err(|_| DeEncryptError::EncryptionError)?,
+
+        Cipher::ChaCha20Poly1305 => <ChaCha20Poly1305 as aes_gcm::KeyInit>::new(key)
+            .encrypt(nonce, data)
+            .map_err(|_| DeEncryptError::EncryptionError)?,
+    };
 
-    Ok(payload.data)
+    Ok((data, hmac))
+}
+
+/// Decrypt data with a cipher, verifying its hmac if integrity was requested
+fn open(
+    cipher: Cipher,
+    integrity: bool,
+    hmac: Option<[u8; 32]>,
+    iv_key: &[u8; 48],
+    data: Vec<u8>,
+) -> Result<Zeroizing<Vec<u8>>, DeEncryptError> {
+    let iv = GenericArray::from_slice(&iv_key[..16]);
+    let key = GenericArray::from_slice(&iv_key[16..]);
+    let nonce = GenericArray::from_slice(&iv_key[..12]);
+
+    match cipher {
+        Cipher::Aes256Ctr => {
+            if integrity {
+                // Verify hmac
+                let hmac = hmac.ok_or(DeEncryptError::IntegrityError)?;
+
+                let mut mac = HmacSha256::new_from_slice(key)?;
+                mac.update(&data);
+                mac.verify_slice(&hmac)
+                    .map_err(|_| DeEncryptError::IntegrityError)?;
+            }
+
+            // Decrypt payload
+            let mut data = Zeroizing::new(data);
+            let mut cipher = Aes256Ctr::new(key, iv);
+            cipher.apply_keystream(&mut data);
+
+            Ok(data)
+        }
+
+        // aead decryption fails closed on any tampering
+        Cipher::Aes256Gcm => <Aes256Gcm as aes_gcm::KeyInit>::new(key)
+            .decrypt(nonce, &*data)
+            .map(Zeroizing::new)
+            .map_err(|_| DeEncryptError::IntegrityError),
+
+        Cipher::ChaCha20Poly1305 => <ChaCha20Poly1305 as aes_gcm::KeyInit>::new(key)
+            .decrypt(nonce, &*data)
+            .map(Zeroizing::new)
+            .map_err(|_| DeEncryptError::IntegrityError),
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -140,6 +544,20 @@ pub enum DeEncryptError {
     IncorrectPassword,
     #[error("Integrity flag does not match the integrity of the underlying data")]
     IncorrectIntegrity,
+    #[error("Invalid key derivation parameters")]
+    InvalidKdfParams,
+    #[error("Failed to encrypt data")]
+    EncryptionError,
+    #[error("Unsupported payload version {0}")]
+    UnsupportedPayloadVersion(u8),
+    #[error("At least one recipient is required")]
+    NoRecipients,
+    #[error("Message was not encrypted for this identity")]
+    NoMatchingIdentity,
+    #[error("Invalid key")]
+    InvalidKey,
+    #[error("Signature is invalid")]
+    InvalidSignature,
 }
 
 impl From<pbkdf2::password_hash::Error> for DeEncryptError {
@@ -157,7 +575,7 @@ mod tests {
         let data = encrypt("123", &[1, 2, 3, 4], false).unwrap();
         let data = decrypt("123", &data).unwrap();
 
-        assert_eq!(data, &[1, 2, 3, 4]);
+        assert_eq!(*data, [1, 2, 3, 4]);
     }
 
     #[test]
@@ -165,7 +583,7 @@ mod tests {
         let data = encrypt("123", &[1, 2, 3, 4], true).unwrap();
         let data = decrypt("123", &data).unwrap();
 
-        assert_eq!(data, &[1, 2, 3, 4]);
+        assert_eq!(*data, [1, 2, 3, 4]);
     }
 
     #[test]
@@ -198,6 +616,296 @@ mod tests {
         assert!(data.is_err());
     }
 
+    #[test]
+    fn test_custom_kdf_rounds() {
+        let params = Params {
+            integrity: true,
+            kdf: Kdf::Pbkdf2 { rounds: 1_000 },
+            ..Default::default()
+        };
+
+        let data = encrypt_with("123", &[1, 2, 3, 4], &params).unwrap();
+        let data = decrypt("123", &data).unwrap();
+
+        assert_eq!(*data, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_aead_ciphers() {
+        for cipher in [Cipher::Aes256Gcm, Cipher::ChaCha20Poly1305] {
+            let params = Params {
+                cipher,
+                ..Default::default()
+            };
+
+            let data = encrypt_with("123", &[1, 2, 3, 4], &params).unwrap();
+            let data = decrypt("123", &data).unwrap();
+
+            assert_eq!(*data, [1, 2, 3, 4]);
+        }
+    }
+
+    #[test]
+    fn test_aead_tampered() {
+        for cipher in [Cipher::Aes256Gcm, Cipher::ChaCha20Poly1305] {
+            let params = Params {
+                cipher,
+                ..Default::default()
+            };
+
+            let mut data = encrypt_with("123", &[1, 2, 3, 4], &params).unwrap();
+            *data.last_mut().unwrap() ^= 1;
+
+            assert!(matches!(
+                decrypt("123", &data),
+                Err(DeEncryptError::IntegrityError)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_encrypted_len() {
+        for cipher in [
+            Cipher::Aes256Ctr,
+            Cipher::Aes256Gcm,
+            Cipher::ChaCha20Poly1305,
+        ] {
+            for integrity in [false, true] {
+                let params = Params {
+                    integrity,
+                    kdf: Kdf::Pbkdf2 { rounds: 1_000 },
+                    cipher,
+                };
+
+                for len in [0, 4, 300] {
+                    let data = encrypt_with("123", &vec![1; len], &params).unwrap();
+                    assert_eq!(encrypted_len(&params, len), data.len());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_argon2id() {
+        let params = Params {
+            kdf: Kdf::Argon2id {
+                m_cost: 256,
+                t_cost: 1,
+                p_cost: 1,
+            },
+            ..Default::default()
+        };
+
+        let data = encrypt_with("123", &[1, 2, 3, 4], &params).unwrap();
+
+        assert_eq!(*decrypt("123", &data).unwrap(), [1, 2, 3, 4]);
+        assert!(matches!(
+            decrypt("1234", &data),
+            Err(DeEncryptError::IncorrectPassword)
+        ));
+    }
+
+    #[test]
+    fn test_argon2id_excessive_cost() {
+        let params = Params {
+            kdf: Kdf::Argon2id {
+                m_cost: MAX_ARGON2_M_COST + 1,
+                t_cost: 1,
+                p_cost: 1,
+            },
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            encrypt_with("123", &[1, 2, 3, 4], &params),
+            Err(DeEncryptError::InvalidKdfParams)
+        ));
+
+        // a crafted payload is refused before anything gets allocated
+        let payload = Payload {
+            version: PAYLOAD_VERSION,
+            kdf: params.kdf,
+            cipher: Cipher::Aes256Ctr,
+            salt: *b"AAAAAAAAAAAAAAAAAAAAAA",
+            key_check: [0; KEY_CHECK_LEN],
+            hmac: None,
+            data: vec![1, 2, 3, 4],
+        };
+        let data = bincode::encode_to_vec(payload, bincode::config::standard()).unwrap();
+
+        assert!(matches!(
+            decrypt("123", &data),
+            Err(DeEncryptError::InvalidKdfParams)
+        ));
+    }
+
+    #[test]
+    fn test_encrypt_zero_rounds() {
+        let params = Params {
+            integrity: false,
+            kdf: Kdf::Pbkdf2 { rounds: 0 },
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            encrypt_with("123", &[1, 2, 3, 4], &params),
+            Err(DeEncryptError::InvalidKdfParams)
+        ));
+    }
+
+    #[test]
+    fn test_corrupted_with_correct_password() {
+        let params = Params {
+            cipher: Cipher::Aes256Gcm,
+            ..Default::default()
+        };
+
+        let mut data = encrypt_with("123", &[1, 2, 3, 4], &params).unwrap();
+        *data.last_mut().unwrap() ^= 1;
+
+        assert!(matches!(
+            decrypt("123", &data),
+            Err(DeEncryptError::IntegrityError)
+        ));
+    }
+
+    #[test]
+    fn test_decrypt_legacy() {
+        // written by 0.1.6, with and without integrity
+        let integrity = [
+            1, 209, 224, 25, 124, 190, 213, 21, 47, 242, 133, 49, 235, 193, 235, 121, 37, 26, 108,
+            13, 201, 160, 211, 221, 209, 114, 27, 30, 124, 216, 87, 47, 67, 78, 69, 75, 68, 76, 74,
+            52, 73, 75, 98, 90, 114, 112, 80, 74, 112, 106, 52, 68, 116, 76, 81, 1, 92, 235, 58,
+            30, 84, 115, 163, 243, 79, 42, 222, 80, 164, 157, 14, 126, 68, 200, 31, 193, 35, 12,
+            147, 5, 199, 236, 188, 122, 202, 117, 72, 199, 4, 73, 82, 126, 156,
+        ];
+        let plain = [
+            0, 150, 236, 12, 185, 216, 148, 216, 138, 99, 74, 84, 172, 143, 4, 172, 188, 207, 232,
+            3, 166, 49, 142, 11, 164, 53, 176, 33, 19, 221, 204, 68, 26, 71, 70, 68, 77, 111, 80,
+            115, 100, 112, 110, 122, 82, 87, 118, 122, 80, 113, 104, 52, 89, 109, 81, 0, 4, 121,
+            180, 19, 254,
+        ];
+
+        for data in [&integrity[..], &plain[..]] {
+            assert_eq!(
+                PayloadVersion::of(data).unwrap(),
+                PayloadVersion::Unversioned
+            );
+            assert_eq!(*decrypt("123", data).unwrap(), [1, 2, 3, 4]);
+            assert!(matches!(
+                decrypt("1234", data),
+                Err(DeEncryptError::IncorrectPassword)
+            ));
+        }
+
+        let mut tampered = integrity;
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            decrypt("123", &tampered),
+            Err(DeEncryptError::IntegrityError)
+        ));
+    }
+
+    #[test]
+    fn test_versions() {
+        // payloads of each version, which must keep decrypting as they are
+        let keyed = [
+            2, 0, 251, 232, 3, 0, 54, 74, 49, 72, 68, 43, 52, 102, 113, 57, 118, 104, 111, 88, 78,
+            51, 71, 69, 110, 86, 68, 65, 216, 121, 0, 4, 168, 146, 204, 141,
+        ];
+        let keyed_aead = [
+            2, 0, 251, 232, 3, 1, 97, 56, 107, 68, 68, 84, 78, 100, 104, 114, 107, 66, 90, 104,
+            121, 78, 113, 87, 52, 78, 80, 65, 2, 198, 0, 20, 84, 245, 112, 86, 176, 74, 108, 212,
+            206, 118, 203, 213, 253, 242, 249, 27, 105, 121, 224, 111,
+        ];
+        let streamed = [
+            3, 0, 251, 232, 3, 89, 112, 109, 114, 51, 76, 84, 112, 89, 88, 76, 82, 100, 82, 109,
+            111, 90, 105, 104, 86, 67, 65, 147, 39, 0, 170, 208, 19, 119,
+        ];
+
+        for (payload, version) in [
+            (&keyed[..], PayloadVersion::Keyed),
+            (&keyed_aead[..], PayloadVersion::Keyed),
+            (&streamed[..], PayloadVersion::Streamed),
+        ] {
+            assert_eq!(PayloadVersion::of(payload).unwrap(), version);
+            assert_eq!(*decrypt("123", payload).unwrap(), [1, 2, 3, 4]);
+        }
+
+        // new payloads are written with the current versions
+        let data = encrypt("123", &[1, 2, 3, 4], false).unwrap();
+        assert_eq!(PayloadVersion::of(&data).unwrap(), PayloadVersion::Keyed);
+    }
+
+    #[test]
+    fn test_downgrade() {
+        // a payload of each suite, with and without an hmac
+        let ctr = [
+            2, 0, 251, 232, 3, 0, 54, 74, 49, 72, 68, 43, 52, 102, 113, 57, 118, 104, 111, 88, 78,
+            51, 71, 69, 110, 86, 68, 65, 216, 121, 0, 4, 168, 146, 204, 141,
+        ];
+        let ctr_hmac = [
+            2, 0, 251, 232, 3, 0, 90, 108, 102, 80, 98, 98, 86, 67, 85, 86, 97, 100, 108, 106, 84,
+            71, 50, 54, 78, 106, 85, 119, 228, 192, 1, 173, 224, 36, 211, 146, 11, 177, 177, 157,
+            90, 222, 234, 147, 173, 141, 123, 145, 106, 178, 154, 254, 65, 75, 241, 183, 125, 204,
+            233, 174, 216, 89, 91, 4, 196, 191, 95, 241,
+        ];
+        let gcm = [
+            2, 0, 251, 232, 3, 1, 97, 56, 107, 68, 68, 84, 78, 100, 104, 114, 107, 66, 90, 104,
+            121, 78, 113, 87, 52, 78, 80, 65, 2, 198, 0, 20, 84, 245, 112, 86, 176, 74, 108, 212,
+            206, 118, 203, 213, 253, 242, 249, 27, 105, 121, 224, 111,
+        ];
+        let chacha = [
+            2, 0, 251, 232, 3, 2, 65, 117, 75, 107, 69, 97, 116, 81, 53, 119, 65, 122, 80, 97, 76,
+            107, 115, 122, 117, 77, 76, 65, 58, 123, 0, 20, 132, 129, 186, 8, 105, 206, 101, 53,
+            190, 206, 54, 222, 112, 157, 19, 146, 227, 85, 89, 250,
+        ];
+
+        for payload in [&ctr[..], &ctr_hmac, &gcm, &chacha] {
+            assert_eq!(*decrypt("123", payload).unwrap(), [1, 2, 3, 4]);
+
+            let tampered = |tamper: &dyn Fn(&mut Payload)| {
+                let (mut payload, _) =
+                    bincode::decode_from_slice::<Payload, _>(payload, bincode::config::standard())
+                        .unwrap();
+                tamper(&mut payload);
+                bincode::encode_to_vec(payload, bincode::config::standard()).unwrap()
+            };
+
+            // none of what the payload says about its encryption can be changed
+            let mut downgrades = [
+                Cipher::Aes256Ctr,
+                Cipher::Aes256Gcm,
+                Cipher::ChaCha20Poly1305,
+            ]
+            .into_iter()
+            .map(|cipher| tampered(&|payload| payload.cipher = cipher))
+            .filter(|tampered| tampered != payload)
+            .collect::<Vec<_>>();
+            downgrades.push(tampered(&|payload| {
+                payload.hmac = payload.hmac.xor(Some([0; 32]));
+            }));
+            downgrades.push(tampered(&|payload| {
+                payload.kdf = Kdf::Pbkdf2 { rounds: 1_001 };
+            }));
+
+            for downgrade in downgrades {
+                assert!(matches!(
+                    decrypt("123", &downgrade),
+                    Err(DeEncryptError::IncorrectPassword | DeEncryptError::IntegrityError)
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsupported_payload_version() {
+        assert!(matches!(
+            decrypt("123", &[stream::PAYLOAD_VERSION + 1]),
+            Err(DeEncryptError::UnsupportedPayloadVersion(_))
+        ));
+    }
+
     #[test]
     fn test_encrypt_no_pass() {
         assert!(encrypt("", &[1, 2, 3, 4], false).is_err());
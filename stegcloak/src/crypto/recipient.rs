@@ -0,0 +1,318 @@
+//! Encrypt to one or more X25519 public keys instead of a password
+//!
+//! A random file key encrypts the data. The file key is then wrapped once per recipient,
+//! using an ephemeral X25519 key exchange, in a stanza stored alongside the data (like age).
+
+use std::{fmt, str::FromStr};
+
+use aes::cipher::generic_array::GenericArray;
+use base64ct::{Base64UrlUnpadded, Encoding as _};
+use bincode::{Decode, Encode};
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305};
+use hkdf::Hkdf;
+use rand::{rngs::OsRng, RngCore as _};
+use sha2::Sha256;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use super::DeEncryptError;
+
+/// Current recipient payload format version
+const PAYLOAD_VERSION: u8 = 1;
+
+const SECRET_KEY_PREFIX: &str = "STEGCLOAK-SECRET-KEY-";
+const PUBLIC_KEY_PREFIX: &str = "stegcloak-pub-";
+
+// every key is only ever used once, so a fixed nonce is fine
+const NONCE: [u8; 12] = [0; 12];
+
+/// A secret key which can reveal messages hidden for its [`Recipient`]
+#[derive(Clone)]
+pub struct Identity(StaticSecret);
+
+impl Identity {
+    /// Generate a new random identity
+    pub fn generate() -> Self {
+        Self(StaticSecret::random_from_rng(OsRng))
+    }
+
+    /// The public key others hide messages for
+    pub fn to_public(&self) -> Recipient {
+        Recipient(PublicKey::from(&self.0))
+    }
+
+    /// Raw secret key bytes
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+}
+
+impl From<[u8; 32]> for Identity {
+    fn from(value: [u8; 32]) -> Self {
+        Self(StaticSecret::from(value))
+    }
+}
+
+impl fmt::Debug for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Identity").field(&"..").finish()
+    }
+}
+
+impl fmt::Display for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = Base64UrlUnpadded::encode_string(self.0.as_bytes());
+        write!(f, "{SECRET_KEY_PREFIX}{key}")
+    }
+}
+
+impl FromStr for Identity {
+    type Err = DeEncryptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_key(s, SECRET_KEY_PREFIX).map(Self::from)
+    }
+}
+
+/// A public key messages can be hidden for
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Recipient(PublicKey);
+
+impl Recipient {
+    /// Raw public key bytes
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+}
+
+impl From<[u8; 32]> for Recipient {
+    fn from(value: [u8; 32]) -> Self {
+        Self(PublicKey::from(value))
+    }
+}
+
+impl fmt::Display for Recipient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = Base64UrlUnpadded::encode_string(self.0.as_bytes());
+        write!(f, "{PUBLIC_KEY_PREFIX}{key}")
+    }
+}
+
+impl FromStr for Recipient {
+    type Err = DeEncryptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_key(s, PUBLIC_KEY_PREFIX).map(Self::from)
+    }
+}
+
+fn parse_key(s: &str, prefix: &str) -> Result<[u8; 32], DeEncryptError> {
+    let key = s
+        .trim()
+        .strip_prefix(prefix)
+        .ok_or(DeEncryptError::InvalidKey)?;
+
+    let mut bytes = [0u8; 32];
+    let decoded =
+        Base64UrlUnpadded::decode(key, &mut bytes).map_err(|_| DeEncryptError::InvalidKey)?;
+
+    if decoded.len() != bytes.len() {
+        return Err(DeEncryptError::InvalidKey);
+    }
+
+    Ok(bytes)
+}
+
+/// File key wrapped for a single recipient
+#[derive(Debug, Encode, Decode)]
+struct Stanza {
+    ephemeral: [u8; 32],
+    wrapped_key: [u8; 48],
+}
+
+#[derive(Debug, Encode, Decode)]
+struct Payload {
+    version: u8,
+    stanzas: Vec<Stanza>,
+    data: Vec<u8>,
+}
+
+/// Encrypt a binary stream so any of the recipients can decrypt it
+///
+/// Requirements:
+/// - recipients.len > 0
+pub fn encrypt(recipients: &[Recipient], data: &[u8]) -> Result<Vec<u8>, DeEncryptError> {
+    if recipients.is_empty() {
+        return Err(DeEncryptError::NoRecipients);
+    }
+
+    let mut file_key = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(&mut *file_key);
+
+    let stanzas = recipients
+        .iter()
+        .map(|recipient| wrap(&file_key, &recipient.0))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let data = cipher(&payload_key(file_key.as_slice()))
+        .encrypt(GenericArray::from_slice(&NONCE), data)
+        .map_err(|_| DeEncryptError::EncryptionError)?;
+
+    let payload = Payload {
+        version: PAYLOAD_VERSION,
+        stanzas,
+        data,
+    };
+
+    let payload = bincode::encode_to_vec(payload, bincode::config::standard())?;
+
+    Ok(payload)
+}
+
+/// Decrypt a binary stream using an identity which it was encrypted for
+///
+/// Every stanza is tried against the identity until one of them opens.
+pub fn decrypt(identity: &Identity, data: &[u8]) -> Result<Zeroizing<Vec<u8>>, DeEncryptError> {
+    let (payload, _) = bincode::decode_from_slice::<Payload, _>(data, bincode::config::standard())?;
+
+    if payload.version != PAYLOAD_VERSION {
+        return Err(DeEncryptError::UnsupportedPayloadVersion(payload.version));
+    }
+
+    let file_key = payload
+        .stanzas
+        .iter()
+        .find_map(|stanza| unwrap(stanza, identity))
+        .ok_or(DeEncryptError::NoMatchingIdentity)?;
+
+    cipher(&payload_key(&file_key))
+        .decrypt(GenericArray::from_slice(&NONCE), &*payload.data)
+        .map(Zeroizing::new)
+        .map_err(|_| DeEncryptError::IntegrityError)
+}
+
+fn cipher(key: &[u8; 32]) -> ChaCha20Poly1305 {
+    <ChaCha20Poly1305 as chacha20poly1305::KeyInit>::new(GenericArray::from_slice(key))
+}
+
+fn payload_key(file_key: &[u8]) -> Zeroizing<[u8; 32]> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(None, file_key)
+        .expand(b"stegcloak payload", &mut *key)
+        .unwrap();
+
+    key
+}
+
+fn wrap_key(shared: &[u8], ephemeral: &PublicKey, recipient: &PublicKey) -> Zeroizing<[u8; 32]> {
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral.as_bytes());
+    salt[32..].copy_from_slice(recipient.as_bytes());
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(b"stegcloak x25519", &mut *key)
+        .unwrap();
+
+    key
+}
+
+fn wrap(file_key: &[u8; 32], recipient: &PublicKey) -> Result<Stanza, DeEncryptError> {
+    let secret = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral = PublicKey::from(&secret);
+
+    let shared = secret.diffie_hellman(recipient);
+    if !shared.was_contributory() {
+        return Err(DeEncryptError::InvalidKey);
+    }
+
+    let wrapped_key = cipher(&wrap_key(shared.as_bytes(), &ephemeral, recipient))
+        .encrypt(GenericArray::from_slice(&NONCE), &file_key[..])
+        .map_err(|_| DeEncryptError::EncryptionError)?;
+
+    Ok(Stanza {
+        ephemeral: ephemeral.to_bytes(),
+        wrapped_key: wrapped_key.try_into().unwrap(),
+    })
+}
+
+fn unwrap(stanza: &Stanza, identity: &Identity) -> Option<Zeroizing<Vec<u8>>> {
+    let ephemeral = PublicKey::from(stanza.ephemeral);
+    let shared = identity.0.diffie_hellman(&ephemeral);
+    if !shared.was_contributory() {
+        return None;
+    }
+
+    let public = PublicKey::from(&identity.0);
+
+    cipher(&wrap_key(shared.as_bytes(), &ephemeral, &public))
+        .decrypt(GenericArray::from_slice(&NONCE), &stanza.wrapped_key[..])
+        .ok()
+        .map(Zeroizing::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiple_recipients() {
+        let alice = Identity::generate();
+        let bob = Identity::generate();
+
+        let data = encrypt(&[alice.to_public(), bob.to_public()], &[1, 2, 3, 4]).unwrap();
+
+        assert_eq!(*decrypt(&alice, &data).unwrap(), [1, 2, 3, 4]);
+        assert_eq!(*decrypt(&bob, &data).unwrap(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_wrong_identity() {
+        let alice = Identity::generate();
+        let eve = Identity::generate();
+
+        let data = encrypt(&[alice.to_public()], &[1, 2, 3, 4]).unwrap();
+
+        assert!(matches!(
+            decrypt(&eve, &data),
+            Err(DeEncryptError::NoMatchingIdentity)
+        ));
+    }
+
+    #[test]
+    fn test_tampered() {
+        let alice = Identity::generate();
+
+        let mut data = encrypt(&[alice.to_public()], &[1, 2, 3, 4]).unwrap();
+        *data.last_mut().unwrap() ^= 1;
+
+        assert!(matches!(
+            decrypt(&alice, &data),
+            Err(DeEncryptError::IntegrityError)
+        ));
+    }
+
+    #[test]
+    fn test_no_recipients() {
+        assert!(matches!(
+            encrypt(&[], &[1, 2, 3, 4]),
+            Err(DeEncryptError::NoRecipients)
+        ));
+    }
+
+    #[test]
+    fn test_key_text_roundtrip() {
+        let identity = Identity::generate();
+        let recipient = identity.to_public();
+
+        let parsed = identity.to_string().parse::<Identity>().unwrap();
+        assert_eq!(parsed.to_bytes(), identity.to_bytes());
+
+        let parsed = recipient.to_string().parse::<Recipient>().unwrap();
+        assert_eq!(parsed, recipient);
+
+        // keys can't be mixed up
+        assert!(recipient.to_string().parse::<Identity>().is_err());
+        assert!(identity.to_string().parse::<Recipient>().is_err());
+    }
+}
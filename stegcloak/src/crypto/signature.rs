@@ -0,0 +1,91 @@
+//! Ed25519 signatures proving who cloaked a message
+//!
+//! The signer's public key and a signature over the header and payload are stored in front of
+//! the payload, so anyone can verify a message without knowing the key in advance.
+
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+
+use ed25519_dalek::{Signature, Signer as _};
+use rand::rngs::OsRng;
+
+use super::DeEncryptError;
+
+/// Size of the stored verifying key and signature in bytes
+pub const SIZE: usize = ed25519_dalek::PUBLIC_KEY_LENGTH + ed25519_dalek::SIGNATURE_LENGTH;
+
+/// Generate a new random signing key
+pub fn generate() -> SigningKey {
+    SigningKey::generate(&mut OsRng)
+}
+
+/// Sign the header and payload, returning the block stored in front of the payload
+pub fn sign(key: &SigningKey, header: &[u8], data: &[u8]) -> [u8; SIZE] {
+    let signature = key.sign(&signed_message(header, data));
+
+    let mut block = [0u8; SIZE];
+    block[..ed25519_dalek::PUBLIC_KEY_LENGTH].copy_from_slice(key.verifying_key().as_bytes());
+    block[ed25519_dalek::PUBLIC_KEY_LENGTH..].copy_from_slice(&signature.to_bytes());
+
+    block
+}
+
+/// Verify the signature block at the start of `data`
+///
+/// Returns the signer and the payload after the signature block
+pub fn verify<'a>(
+    header: &[u8],
+    data: &'a [u8],
+) -> Result<(VerifyingKey, &'a [u8]), DeEncryptError> {
+    if data.len() < SIZE {
+        return Err(DeEncryptError::InvalidSignature);
+    }
+
+    let (block, data) = data.split_at(SIZE);
+    let (key, signature) = block.split_at(ed25519_dalek::PUBLIC_KEY_LENGTH);
+
+    let key = VerifyingKey::try_from(key).map_err(|_| DeEncryptError::InvalidSignature)?;
+    let signature =
+        Signature::from_slice(signature).map_err(|_| DeEncryptError::InvalidSignature)?;
+
+    key.verify_strict(&signed_message(header, data), &signature)
+        .map_err(|_| DeEncryptError::InvalidSignature)?;
+
+    Ok((key, data))
+}
+
+fn signed_message(header: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(header.len() + data.len());
+    message.extend(header);
+    message.extend(data);
+
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_verify() {
+        let key = generate();
+
+        let mut data = sign(&key, &[1, 2], &[3, 4]).to_vec();
+        data.extend([3, 4]);
+
+        let (signer, rest) = verify(&[1, 2], &data).unwrap();
+        assert_eq!(signer, key.verifying_key());
+        assert_eq!(rest, &[3, 4]);
+
+        // header is covered by the signature too
+        assert!(matches!(
+            verify(&[1, 3], &data),
+            Err(DeEncryptError::InvalidSignature)
+        ));
+
+        *data.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            verify(&[1, 2], &data),
+            Err(DeEncryptError::InvalidSignature)
+        ));
+    }
+}
@@ -0,0 +1,395 @@
+//! Incremental AES-256-CTR encryption over `std::io`, with bounded memory
+//!
+//! The stream payload can't store the length of the data up front, so the data runs to the end
+//! of the payload, followed by the hmac if integrity is enabled. Aead ciphers need the whole
+//! message and aren't supported.
+
+use std::io::{self, Read, Write};
+
+use aes::cipher::{generic_array::GenericArray, KeyIvInit as _, StreamCipher as _};
+use bincode::{Decode, Encode};
+use hmac::Mac as _;
+use pbkdf2::password_hash::SaltString;
+use rand::rngs::OsRng;
+use zeroize::Zeroizing;
+
+use super::{
+    derive_keys, open, Aes256Ctr, Cipher, DeEncryptError, HmacSha256, Kdf, Suite, KEY_CHECK_LEN,
+};
+
+/// Payload version of streamed payloads
+pub const PAYLOAD_VERSION: u8 = 3;
+
+const HMAC_LEN: usize = 32;
+
+#[derive(Debug, Encode, Decode)]
+struct StreamPayload {
+    version: u8,
+    kdf: Kdf,
+    salt: [u8; 22],
+    key_check: [u8; KEY_CHECK_LEN],
+    integrity: bool,
+}
+
+impl StreamPayload {
+    /// Derive the keys, checking the password
+    fn derive(&self, password: &str) -> Result<Zeroizing<[u8; 48]>, DeEncryptError> {
+        let salt = SaltString::from_b64(std::str::from_utf8(&self.salt)?)?;
+        let suite = suite(self.kdf, self.integrity);
+        let (iv_key, key_check) = derive_keys(&suite, password, salt.as_str().as_bytes())?;
+
+        if key_check != self.key_check {
+            return Err(DeEncryptError::IncorrectPassword);
+        }
+
+        Ok(iv_key)
+    }
+
+    fn keys(&self, password: &str) -> Result<(Aes256Ctr, Option<HmacSha256>), DeEncryptError> {
+        let iv_key = self.derive(password)?;
+        cipher(&iv_key, self.integrity)
+    }
+}
+
+/// The suite of streamed payloads, which only vary in kdf and integrity
+fn suite(kdf: Kdf, integrity: bool) -> Suite {
+    Suite {
+        version: PAYLOAD_VERSION,
+        kdf,
+        cipher: Cipher::Aes256Ctr,
+        integrity,
+    }
+}
+
+fn cipher(
+    iv_key: &[u8; 48],
+    integrity: bool,
+) -> Result<(Aes256Ctr, Option<HmacSha256>), DeEncryptError> {
+    let iv = GenericArray::from_slice(&iv_key[..16]);
+    let key = GenericArray::from_slice(&iv_key[16..]);
+
+    let mac = if integrity {
+        Some(HmacSha256::new_from_slice(key)?)
+    } else {
+        None
+    };
+
+    Ok((Aes256Ctr::new(key, iv), mac))
+}
+
+/// Encrypts data written to it, writing the payload to the inner writer
+///
+/// Call [`EncryptWriter::finish`] when done, so the hmac gets written
+pub struct EncryptWriter<W: Write> {
+    inner: W,
+    cipher: Aes256Ctr,
+    mac: Option<HmacSha256>,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> EncryptWriter<W> {
+    /// Derive the keys and write the payload prefix to `inner`
+    pub fn new(
+        password: &str,
+        integrity: bool,
+        kdf: Kdf,
+        mut inner: W,
+    ) -> Result<Self, DeEncryptError> {
+        if password.is_empty() {
+            return Err(DeEncryptError::PasswordTooShort);
+        }
+
+        let salt = SaltString::generate(&mut OsRng);
+        let suite = suite(kdf, integrity);
+        let (iv_key, key_check) = derive_keys(&suite, password, salt.as_str().as_bytes())?;
+
+        let payload = StreamPayload {
+            version: PAYLOAD_VERSION,
+            kdf,
+            salt: salt.as_str().as_bytes().try_into().unwrap(),
+            key_check,
+            integrity,
+        };
+
+        bincode::encode_into_std_write(payload, &mut inner, bincode::config::standard())?;
+
+        let (cipher, mac) = cipher(&iv_key, integrity)?;
+
+        Ok(Self {
+            inner,
+            cipher,
+            mac,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Write the hmac and return the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(mac) = self.mac.take() {
+            self.inner.write_all(&mac.finalize().into_bytes())?;
+        }
+
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.clear();
+        self.buffer.extend(buf);
+        self.cipher.apply_keystream(&mut self.buffer);
+
+        if let Some(mac) = &mut self.mac {
+            mac.update(&self.buffer);
+        }
+
+        self.inner.write_all(&self.buffer)?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decrypts a streamed payload read from the inner reader
+///
+/// Decrypted data is returned before the hmac at the end is verified. If it doesn't match, the
+/// last read fails with [`io::ErrorKind::InvalidData`] wrapping [`DeEncryptError::IntegrityError`].
+pub struct DecryptReader<R: Read> {
+    inner: R,
+    cipher: Aes256Ctr,
+    mac: Option<HmacSha256>,
+    // the last bytes read, which might be the hmac
+    tail: Vec<u8>,
+    done: bool,
+}
+
+impl<R: Read> DecryptReader<R> {
+    /// Read the payload prefix from `inner` and derive the keys
+    pub fn new(password: &str, inner: R) -> Result<Self, DeEncryptError> {
+        Self::open(password, None, inner)
+    }
+
+    /// Like [`DecryptReader::new`], but fails with [`DeEncryptError::IntegrityError`] unless the
+    /// payload says it carries an hmac exactly when `integrity` is set
+    ///
+    /// The flag is bound into the keys anyway, this tells a stripped hmac apart from a wrong
+    /// password before spending time on the kdf.
+    pub fn with_integrity(
+        password: &str,
+        integrity: bool,
+        inner: R,
+    ) -> Result<Self, DeEncryptError> {
+        Self::open(password, Some(integrity), inner)
+    }
+
+    fn open(password: &str, integrity: Option<bool>, mut inner: R) -> Result<Self, DeEncryptError> {
+        if password.is_empty() {
+            return Err(DeEncryptError::PasswordTooShort);
+        }
+
+        let payload: StreamPayload =
+            bincode::decode_from_std_read(&mut inner, bincode::config::standard())?;
+
+        if payload.version != PAYLOAD_VERSION {
+            return Err(DeEncryptError::UnsupportedPayloadVersion(payload.version));
+        }
+
+        if integrity.is_some_and(|integrity| integrity != payload.integrity) {
+            return Err(DeEncryptError::IntegrityError);
+        }
+
+        let (cipher, mac) = payload.keys(password)?;
+
+        Ok(Self {
+            inner,
+            cipher,
+            mac,
+            tail: Vec::with_capacity(HMAC_LEN),
+            done: false,
+        })
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(mac) = &mut self.mac else {
+            let n = self.inner.read(buf)?;
+            self.cipher.apply_keystream(&mut buf[..n]);
+            return Ok(n);
+        };
+
+        loop {
+            if self.done || buf.is_empty() {
+                return Ok(0);
+            }
+
+            let n = self.inner.read(buf)?;
+            if n == 0 {
+                self.done = true;
+
+                let mac = mac.clone();
+                return match mac.verify_slice(&self.tail) {
+                    Ok(()) => Ok(0),
+                    Err(_) => Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        DeEncryptError::IntegrityError,
+                    )),
+                };
+            }
+
+            // hold back the last bytes, since they might be the hmac
+            let mut data = std::mem::take(&mut self.tail);
+            data.extend(&buf[..n]);
+            self.tail = data.split_off(data.len().saturating_sub(HMAC_LEN));
+
+            if data.is_empty() {
+                continue;
+            }
+
+            let len = data.len();
+            buf[..len].copy_from_slice(&data);
+            mac.update(&buf[..len]);
+            self.cipher.apply_keystream(&mut buf[..len]);
+
+            return Ok(len);
+        }
+    }
+}
+
+/// Whether a whole streamed payload says it carries an hmac
+pub(super) fn integrity(data: &[u8]) -> Result<bool, DeEncryptError> {
+    let (payload, _) =
+        bincode::decode_from_slice::<StreamPayload, _>(data, bincode::config::standard())?;
+
+    Ok(payload.integrity)
+}
+
+/// Decrypt a whole streamed payload
+pub(super) fn decrypt(password: &str, data: &[u8]) -> Result<Zeroizing<Vec<u8>>, DeEncryptError> {
+    let (payload, len) =
+        bincode::decode_from_slice::<StreamPayload, _>(data, bincode::config::standard())?;
+
+    let iv_key = payload.derive(password)?;
+
+    let mut data = &data[len..];
+    let mut hmac = None;
+    if payload.integrity {
+        let Some(split) = data.len().checked_sub(HMAC_LEN) else {
+            return Err(DeEncryptError::IntegrityError);
+        };
+
+        let (rest, tag) = data.split_at(split);
+        hmac = Some(tag.try_into().unwrap());
+        data = rest;
+    }
+
+    open(
+        Cipher::Aes256Ctr,
+        payload.integrity,
+        hmac,
+        &iv_key,
+        data.to_vec(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KDF: Kdf = Kdf::Pbkdf2 { rounds: 1_000 };
+
+    fn encrypt(integrity: bool, data: &[u8]) -> Vec<u8> {
+        let mut writer = EncryptWriter::new("123", integrity, KDF, Vec::new()).unwrap();
+        for chunk in data.chunks(5) {
+            writer.write_all(chunk).unwrap();
+        }
+
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let data = (0..=255).collect::<Vec<u8>>();
+
+        for integrity in [false, true] {
+            let payload = encrypt(integrity, &data);
+
+            let mut decrypted = Vec::new();
+            DecryptReader::new("123", &payload[..])
+                .unwrap()
+                .read_to_end(&mut decrypted)
+                .unwrap();
+            assert_eq!(decrypted, data);
+
+            // whole payloads decrypt through the regular api too
+            assert_eq!(*super::super::decrypt("123", &payload).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_tampered() {
+        let mut payload = encrypt(true, &[1, 2, 3, 4]);
+        let len = payload.len();
+        payload[len - HMAC_LEN - 1] ^= 1;
+
+        let mut reader = DecryptReader::new("123", &payload[..]).unwrap();
+        assert!(reader.read_to_end(&mut Vec::new()).is_err());
+
+        assert!(matches!(
+            super::super::decrypt("123", &payload),
+            Err(DeEncryptError::IntegrityError)
+        ));
+    }
+
+    #[test]
+    fn test_integrity_stripped() {
+        let payload = [
+            3, 0, 251, 232, 3, 107, 112, 73, 43, 81, 105, 107, 75, 88, 90, 55, 108, 47, 106, 87,
+            110, 118, 81, 118, 54, 65, 103, 229, 236, 1, 25, 60, 103, 204, 200, 226, 16, 60, 203,
+            27, 134, 0, 84, 235, 193, 165, 53, 91, 221, 193, 48, 224, 115, 69, 55, 179, 151, 141,
+            247, 12, 68, 32, 83, 150, 49, 140,
+        ];
+        let (_, len) =
+            bincode::decode_from_slice::<StreamPayload, _>(&payload, bincode::config::standard())
+                .unwrap();
+
+        let mut decrypted = Vec::new();
+        DecryptReader::with_integrity("123", true, &payload[..])
+            .unwrap()
+            .read_to_end(&mut decrypted)
+            .unwrap();
+        assert_eq!(decrypted, [1, 2, 3, 4]);
+
+        // the integrity flag cleared and the hmac cut off
+        let mut stripped = payload[..payload.len() - HMAC_LEN].to_vec();
+        stripped[len - 1] = 0;
+
+        assert!(matches!(
+            DecryptReader::with_integrity("123", true, &stripped[..]),
+            Err(DeEncryptError::IntegrityError)
+        ));
+        assert!(matches!(
+            DecryptReader::new("123", &stripped[..]),
+            Err(DeEncryptError::IncorrectPassword)
+        ));
+        assert!(matches!(
+            super::super::decrypt("123", &stripped),
+            Err(DeEncryptError::IncorrectPassword)
+        ));
+    }
+
+    #[test]
+    fn test_incorrect_password() {
+        let payload = encrypt(false, &[1, 2, 3, 4]);
+
+        assert!(matches!(
+            DecryptReader::new("456", &payload[..]),
+            Err(DeEncryptError::IncorrectPassword)
+        ));
+    }
+}
@@ -0,0 +1,197 @@
+use std::ops::Range;
+
+use crate::codec::{self, Alphabet, FoundStream};
+use crate::fec;
+use crate::header::{Header, HeaderError, Mode};
+
+/// A hidden stream found in a text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detection {
+    /// Byte range of the stream in the text
+    pub range: Range<usize>,
+    /// Alphabet the stream is encoded with
+    pub alphabet: Alphabet,
+    /// Size of the hidden payload in bytes, estimated from the stream length if it's malformed
+    pub payload_size: usize,
+    /// Whether the header says the payload is encrypted
+    pub encrypted: bool,
+}
+
+/// Find hidden streams of any built-in profile in a text without revealing them
+///
+/// # Examples
+///
+/// ```rust
+///     let hidden = stegcloak::plaintext::hide("mysecret", "cover text").unwrap();
+///
+///     let detections = stegcloak::detect(&hidden);
+///     assert_eq!(detections.len(), 1);
+///     assert!(!detections[0].encrypted);
+/// ```
+pub fn detect(text: &str) -> Vec<Detection> {
+    codec::find_all(text)
+        .into_iter()
+        .map(|(profile, stream)| detection(text, &profile.alphabet(), stream))
+        .collect()
+}
+
+/// Find hidden streams encoded with a custom alphabet
+pub fn detect_with(text: &str, alphabet: &Alphabet) -> Vec<Detection> {
+    codec::find_all_with(text, alphabet)
+        .into_iter()
+        .map(|stream| detection(text, alphabet, stream))
+        .collect()
+}
+
+fn detection(text: &str, alphabet: &Alphabet, (range, decoded): FoundStream) -> Detection {
+    let (payload_size, encrypted) = match decoded {
+        Ok(data) => {
+            // error corrected streams hold the header inside their frames
+            let unframed = fec::unframe(&data).map(|(data, _)| match Header::decode(&data) {
+                Ok((header, _)) => header.mode != Mode::Plaintext,
+                // headerless streams from 0.1.6 and before start with the integrity flag if
+                // encrypted
+                Err(HeaderError::Missing) => matches!(data.first(), Some(0 | 1)),
+                Err(_) => false,
+            });
+            let encrypted = unframed.unwrap_or(false);
+
+            (data.len(), encrypted)
+        }
+
+        Err(_) => (codec::estimate_len(&text[range.clone()], alphabet), false),
+    };
+
+    Detection {
+        range,
+        alphabet: *alphabet,
+        payload_size,
+        encrypted,
+    }
+}
+
+/// Remove the hidden streams of every built-in profile, leaving only the cover text
+///
+/// Only streams which decode are removed, the same chars also have regular uses, like joiners
+/// in emoji sequences or a byte order mark.
+///
+/// # Examples
+///
+/// ```rust
+///     let hidden = stegcloak::plaintext::hide("mysecret", "cover text").unwrap();
+///
+///     assert_eq!(stegcloak::strip(&hidden), "cover text");
+/// ```
+pub fn strip(text: &str) -> String {
+    let substituted = codec::find_substituted(text)
+        .into_iter()
+        .map(|(codec, (range, _))| (codec, range))
+        .collect::<Vec<_>>();
+
+    let streams = codec::find_all(text)
+        .into_iter()
+        .filter(|(_, (range, decoded))| {
+            decoded.as_ref().is_ok_and(|data| !data.is_empty())
+                && !substituted.iter().any(|(_, r)| r == range)
+        })
+        .map(|(profile, (range, _))| (profile.alphabet(), range))
+        .collect::<Vec<_>>();
+
+    text.char_indices()
+        // fragmented streams span the cover text between their fragments
+        .filter(|&(i, c)| {
+            !streams
+                .iter()
+                .any(|(alphabet, range)| range.contains(&i) && codec::is_stream_char(c, alphabet))
+        })
+        // substituted chars go back to the regular ones
+        .map(|(i, c)| {
+            substituted
+                .iter()
+                .filter(|(_, range)| range.contains(&i))
+                .fold(c, |c, &(codec, _)| codec::restore(c, codec))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encrypt, plaintext};
+
+    #[test]
+    fn test_detect() {
+        let hidden = encrypt::hide("mysecret", "123", false, "cover text").unwrap();
+        let detections = detect(&hidden);
+
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].range.start, "cover ".len());
+        assert_eq!(&hidden[detections[0].range.end..], "text");
+        assert!(detections[0].encrypted);
+        assert!(detections[0].payload_size > 0);
+
+        let hidden = plaintext::hide("mysecret", "cover text").unwrap();
+        assert!(!detect(&hidden)[0].encrypted);
+
+        assert!(detect("cover text").is_empty());
+    }
+
+    #[test]
+    fn test_estimate() {
+        use codec::{Codec, Profile};
+
+        let alphabet = Profile::JoinersOnly.alphabet();
+        let data = (0..100).collect::<Vec<u8>>();
+
+        // base4 comes out smaller for its shortened pairs
+        for (codec, min) in [
+            (Codec::Base4, 75),
+            (Codec::Radix, 90),
+            (Codec::Whitespace, 90),
+            (Codec::Homoglyphs, 90),
+        ] {
+            let encoded = codec::encode_as(&data, codec, &alphabet);
+
+            // only some cuts leave a stream which doesn't decode
+            let malformed = (1..8)
+                .map(|cut| &encoded[..encoded.char_indices().nth_back(cut).unwrap().0])
+                .map(|cut| format!("{cut}."))
+                .filter(|text| matches!(codec::find_all_with(text, &alphabet)[..], [(_, Err(_))]))
+                .collect::<Vec<_>>();
+            assert!(!malformed.is_empty(), "{codec:?}");
+
+            for text in malformed {
+                let size = detect_with(&text, &alphabet)[0].payload_size;
+                assert!((min..100).contains(&size), "{codec:?} {size}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_strip() {
+        let hidden = plaintext::hide("mysecret", "cover text").unwrap();
+
+        assert_eq!(strip(&hidden), "cover text");
+        assert_eq!(strip("cover text"), "cover text");
+
+        let hidden = crate::StegCloak::new()
+            .codec(codec::Codec::VariationSelectors)
+            .hide("mysecret", None, "cover text \u{2764}\u{fe0f}")
+            .unwrap();
+        assert_eq!(strip(&hidden), "cover text \u{2764}\u{fe0f}");
+
+        let cover = "some longer cover text";
+        let hidden = crate::StegCloak::new()
+            .placement(crate::Placement::Spread)
+            .hide("mysecret", None, cover)
+            .unwrap();
+        assert_eq!(strip(&hidden), cover);
+
+        // alphabet chars with regular uses stay, unless they're part of a stream
+        let cover = "family \u{1f468}\u{200d}\u{1f469} \u{feff}bom a\u{200b}b \u{34f}\u{2060}";
+        assert_eq!(strip(cover), cover);
+
+        let hidden = plaintext::hide("mysecret", cover).unwrap();
+        assert_eq!(strip(&hidden), cover);
+    }
+}
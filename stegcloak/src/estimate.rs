@@ -0,0 +1,211 @@
+use std::fmt;
+
+use crate::codec::{self, Alphabet, Codec};
+use crate::StegCloak;
+
+/// Length a hidden secret adds to the cover text
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Estimate {
+    /// Unicode code points
+    pub chars: usize,
+    /// UTF-8 bytes
+    pub utf8: usize,
+    /// UTF-16 code units, which is what JavaScript counts
+    pub utf16: usize,
+    /// Whether the lengths are exact, or an upper bound
+    ///
+    /// Encrypted streams encode a random salt and nonce, which shorten base4 streams and pick
+    /// chars of different widths by chance. Fragmented streams depend on the spaces of the cover
+    /// text.
+    pub exact: bool,
+}
+
+impl Estimate {
+    /// Length `data` adds to a cover text once encoded
+    ///
+    /// * `random` - Whether `data` holds random bytes, which may encode to other chars next time
+    /// * `fragmented` - Whether the stream may get split into fragments, adding a count prefix
+    /// * `framed` - Whether `data` is error corrected and encoded with [`codec::encode_framed`]
+    pub(crate) fn measure(
+        data: &[u8],
+        random: bool,
+        fragmented: bool,
+        framed: bool,
+        codec: Codec,
+        alphabet: &Alphabet,
+    ) -> Self {
+        let stream = if framed {
+            codec::encode_framed(data, alphabet)
+        } else {
+            codec::encode_as(data, codec, alphabet)
+        };
+        let encoded = if fragmented {
+            codec::fragment(&stream, usize::MAX, alphabet).concat()
+        } else {
+            stream.clone()
+        };
+
+        let chars = if random {
+            // every char as wide as the codec allows
+            let prefix = encoded.chars().count() - stream.chars().count();
+            // frames always take as many digits
+            let max = prefix
+                + if framed {
+                    stream.chars().count()
+                } else {
+                    codec::max_chars(data.len(), codec, alphabet)
+                };
+            vec![codec.widest(alphabet); max]
+        } else {
+            encoded.chars().collect()
+        };
+
+        let exact = !random && !fragmented;
+        if !codec.substitutes() {
+            return Self {
+                chars: chars.len(),
+                utf8: chars.iter().map(|c| c.len_utf8()).sum(),
+                utf16: chars.iter().map(|c| c.len_utf16()).sum(),
+                exact,
+            };
+        }
+
+        // substituted chars replace those of the cover text, only their width adds
+        let restored = chars.iter().map(|&c| codec::restore(c, codec));
+        Self {
+            chars: 0,
+            utf8: chars.iter().map(|c| c.len_utf8()).sum::<usize>()
+                - restored.clone().map(char::len_utf8).sum::<usize>(),
+            utf16: chars.iter().map(|c| c.len_utf16()).sum::<usize>()
+                - restored.map(char::len_utf16).sum::<usize>(),
+            exact,
+        }
+    }
+}
+
+/// Length limit of a platform, in the unit it counts
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Limit {
+    /// Unicode code points
+    Chars(usize),
+    /// UTF-8 bytes
+    Utf8(usize),
+    /// UTF-16 code units
+    Utf16(usize),
+}
+
+impl Limit {
+    /// Length of `text` in the unit of this limit
+    pub fn measure(&self, text: &str) -> usize {
+        match self {
+            Limit::Chars(_) => text.chars().count(),
+            Limit::Utf8(_) => text.len(),
+            Limit::Utf16(_) => text.encode_utf16().count(),
+        }
+    }
+
+    /// The highest allowed length
+    pub fn max(&self) -> usize {
+        match *self {
+            Limit::Chars(max) | Limit::Utf8(max) | Limit::Utf16(max) => max,
+        }
+    }
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Chars(max) => write!(f, "{max} chars"),
+            Limit::Utf8(max) => write!(f, "{max} UTF-8 bytes"),
+            Limit::Utf16(max) => write!(f, "{max} UTF-16 units"),
+        }
+    }
+}
+
+/// Estimate the length hiding a secret adds to the cover text, without a cover text
+///
+/// Same as [`StegCloak::estimate`]
+///
+/// # Examples
+///
+/// ```rust
+///     use stegcloak::StegCloak;
+///
+///     let estimate = stegcloak::estimate("mysecret", &StegCloak::new(), false);
+///     let hidden = stegcloak::plaintext::hide("mysecret", "cover text").unwrap();
+///
+///     assert!(estimate.exact);
+///     assert_eq!(estimate.chars, hidden.chars().count() - "cover text".len());
+/// ```
+pub fn estimate(secret: impl AsRef<[u8]>, options: &StegCloak, encrypted: bool) -> Estimate {
+    options.estimate(secret, encrypted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{codec::Profile, Placement};
+
+    const COVER: &str = "The quick brown fox jumps over the lazy dog. ";
+
+    /// What hiding actually added to the cover text
+    fn added(hidden: &str, cover: &str) -> Estimate {
+        Estimate {
+            chars: hidden.chars().count() - cover.chars().count(),
+            utf8: hidden.len() - cover.len(),
+            utf16: hidden.encode_utf16().count() - cover.encode_utf16().count(),
+            exact: true,
+        }
+    }
+
+    #[test]
+    fn test_exact() {
+        let cover = COVER.repeat(20);
+
+        for codec in [
+            Codec::Base4,
+            Codec::Radix,
+            Codec::VariationSelectors,
+            Codec::TagCharacters,
+            Codec::Homoglyphs,
+            Codec::Whitespace,
+        ] {
+            let steg = StegCloak::new().codec(codec).alphabet(Profile::JoinersOnly);
+
+            let hidden = steg.hide("mysecret", None, &cover).unwrap();
+            let estimate = estimate("mysecret", &steg, false);
+            assert_eq!(estimate, added(&hidden, &cover), "{codec:?}");
+        }
+    }
+
+    #[test]
+    fn test_upper_bound() {
+        let cover = COVER.repeat(20);
+
+        for codec in [Codec::Base4, Codec::Radix, Codec::Homoglyphs] {
+            for placement in [Placement::FirstSpace, Placement::Spread] {
+                let steg = StegCloak::new().codec(codec).placement(placement);
+                let estimate = steg.estimate("mysecret", true);
+                assert!(!estimate.exact);
+
+                for _ in 0..5 {
+                    let hidden = steg.hide("mysecret", Some("mypassword"), &cover).unwrap();
+                    let added = added(&hidden, &cover);
+
+                    assert!(added.chars <= estimate.chars, "{codec:?} {placement:?}");
+                    assert!(added.utf8 <= estimate.utf8, "{codec:?} {placement:?}");
+                    assert!(added.utf16 <= estimate.utf16, "{codec:?} {placement:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_limit() {
+        let text = "a\u{e9}\u{1f980}";
+        assert_eq!(Limit::Chars(0).measure(text), 3);
+        assert_eq!(Limit::Utf8(0).measure(text), 7);
+        assert_eq!(Limit::Utf16(0).measure(text), 4);
+        assert_eq!(Limit::Utf16(280).to_string(), "280 UTF-16 units");
+    }
+}
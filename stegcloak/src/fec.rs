@@ -0,0 +1,236 @@
+//! Reed-Solomon error correction over the hidden bytes
+//!
+//! The header and data are split into small shards, each with a checksum. A shard with a wrong
+//! checksum is treated as erased and rebuilt from the parity shards. Every shard is encoded as a
+//! frame of its own, see [`crate::codec::encode_framed`], so a dropped or duplicated char only
+//! costs the shard it falls in instead of shifting everything after it.
+
+use std::borrow::Cow;
+
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use sha2::{Digest as _, Sha256};
+
+use crate::header;
+
+/// Size of a shard's data in bytes
+const SHARD: usize = 32;
+
+/// Size of a shard's checksum in bytes
+const CHECKSUM: usize = 2;
+
+/// Size of a shard with its checksum, which makes up a frame
+pub const FRAME: usize = CHECKSUM + SHARD;
+
+/// Most data shards protected together, so a group stays within the 256 shards GF(2^8) allows
+const GROUP: usize = 64;
+
+/// Bytes of the data length, u32 LE, which is protected along with the data
+const LEN: usize = 4;
+
+/// Leading shards the redundancy is looked for in, any intact one of them will do
+const PROBE: usize = 8;
+
+/// Highest redundancy, in percent of the data shards
+pub const MAX_REDUNDANCY: u8 = 100;
+
+/// Number of parity shards for `data` data shards
+fn parity_shards(data: usize, redundancy: u8) -> usize {
+    (data * redundancy.min(MAX_REDUNDANCY) as usize).div_ceil(100)
+}
+
+/// The redundancy is part of the checksum, so it's recovered along with the header it's
+/// recorded in
+fn checksum(index: usize, redundancy: u8, shard: &[u8]) -> [u8; CHECKSUM] {
+    let hash = Sha256::new()
+        .chain_update((index as u32).to_le_bytes())
+        .chain_update([redundancy])
+        .chain_update(shard)
+        .finalize();
+
+    [hash[0], hash[1]]
+}
+
+/// Number of data shards in each group of `shards` shards, if that's how many `redundancy`
+/// adds up to
+fn groups(shards: usize, redundancy: u8) -> Option<Vec<usize>> {
+    let full = GROUP + parity_shards(GROUP, redundancy);
+
+    let mut groups = vec![GROUP; shards / full];
+    match shards % full {
+        0 if shards > 0 => (),
+        // the last group is shorter, find the split which adds up to its length
+        rest => groups.push((1..=GROUP).find(|&d| d + parity_shards(d, redundancy) == rest)?),
+    }
+
+    Some(groups)
+}
+
+/// Split `data` into frames with `redundancy` percent parity shards, after [`header::FRAMED`]
+///
+/// # Panics
+///
+/// If `redundancy` is 0, which isn't error correction.
+pub fn encode(data: &[u8], redundancy: u8) -> Vec<u8> {
+    assert!(redundancy > 0, "redundancy must be at least 1%");
+
+    let mut padded = (data.len() as u32).to_le_bytes().to_vec();
+    padded.extend(data);
+    padded.resize(padded.len().div_ceil(SHARD) * SHARD, 0);
+
+    let shards = padded.chunks(SHARD).collect::<Vec<_>>();
+
+    let mut out = vec![header::FRAMED];
+    let mut index = 0;
+    for group in shards.chunks(GROUP) {
+        let parity = parity_shards(group.len(), redundancy);
+
+        let mut group = group.iter().map(|s| s.to_vec()).collect::<Vec<_>>();
+        group.resize(group.len() + parity, vec![0; SHARD]);
+
+        ReedSolomon::new(group.len() - parity, parity)
+            .unwrap()
+            .encode(&mut group)
+            .unwrap();
+
+        for shard in group {
+            out.extend(checksum(index, redundancy, &shard));
+            out.extend(shard);
+            index += 1;
+        }
+    }
+
+    out
+}
+
+/// Repair and strip the parity shards of frames [`encode`] wrote, if `data` starts with
+/// [`header::FRAMED`]
+///
+/// Returns the data and the number of corrupted shards, including parity ones
+pub fn unframe(data: &[u8]) -> Result<(Cow<'_, [u8]>, usize), FecError> {
+    match data.split_first() {
+        Some((&header::FRAMED, frames)) => {
+            let (data, corrections) = decode(frames)?;
+            Ok((Cow::Owned(data), corrections))
+        }
+        _ => Ok((Cow::Borrowed(data), 0)),
+    }
+}
+
+/// Repair and strip the parity shards of the frames after [`header::FRAMED`]
+///
+/// The redundancy is the one most shards have a matching checksum for, out of those which
+/// match any of the leading shards.
+fn decode(frames: &[u8]) -> Result<(Vec<u8>, usize), FecError> {
+    let shards = frames.chunks(FRAME).collect::<Vec<_>>();
+    if shards.is_empty() {
+        return Err(FecError::Truncated);
+    }
+
+    let valid = |i: usize, redundancy: u8| match shards[i].split_at_checked(CHECKSUM) {
+        Some((sum, shard)) => shard.len() == SHARD && sum == checksum(i, redundancy, shard),
+        None => false,
+    };
+
+    let redundancy = (1..=MAX_REDUNDANCY)
+        .filter(|&redundancy| groups(shards.len(), redundancy).is_some())
+        .filter(|&redundancy| (0..shards.len().min(PROBE)).any(|i| valid(i, redundancy)))
+        .max_by_key(|&redundancy| (0..shards.len()).filter(|&i| valid(i, redundancy)).count())
+        .ok_or(FecError::TooManyErrors)?;
+
+    let mut out = Vec::new();
+    let mut corrections = 0;
+    let mut index = 0;
+    for data_shards in groups(shards.len(), redundancy).unwrap() {
+        let parity = parity_shards(data_shards, redundancy);
+
+        let mut group = (index..index + data_shards + parity)
+            .map(|i| valid(i, redundancy).then(|| shards[i][CHECKSUM..].to_vec()))
+            .collect::<Vec<_>>();
+        index += group.len();
+
+        let erased = group.iter().filter(|shard| shard.is_none()).count();
+        if erased > parity {
+            return Err(FecError::TooManyErrors);
+        }
+
+        corrections += erased;
+        if erased > 0 {
+            ReedSolomon::new(data_shards, parity)
+                .unwrap()
+                .reconstruct_data(&mut group)
+                .map_err(|_| FecError::TooManyErrors)?;
+        }
+
+        out.extend(group.into_iter().take(data_shards).flatten().flatten());
+    }
+
+    let (len, rest) = out.split_at_checked(LEN).ok_or(FecError::Truncated)?;
+    let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+
+    let data = rest.get(..len).ok_or(FecError::Truncated)?.to_vec();
+    Ok((data, corrections))
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum FecError {
+    #[error("Error corrected data is truncated")]
+    Truncated,
+    #[error("Too many errors to correct")]
+    TooManyErrors,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(data: &[u8]) -> Result<(Vec<u8>, usize), FecError> {
+        unframe(data).map(|(data, corrections)| (data.into_owned(), corrections))
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for len in [0, 1, 100, 2000, 5000] {
+            let data = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+
+            for redundancy in [1, 25, 100] {
+                let encoded = encode(&data, redundancy);
+                assert_eq!((encoded.len() - 1) % FRAME, 0);
+                assert_eq!(decode(&encoded), Ok((data.clone(), 0)));
+            }
+        }
+
+        assert!(matches!(
+            unframe(&[1, 2, 3]),
+            Ok((Cow::Borrowed(&[1, 2, 3]), 0))
+        ));
+    }
+
+    #[test]
+    fn test_corrections() {
+        // 7 data shards and 2 parity shards
+        let data = (0..200).map(|i| i as u8).collect::<Vec<_>>();
+        let mut encoded = encode(&data, 25);
+        assert_eq!(encoded.len(), 1 + 9 * FRAME);
+
+        // the first shard holds the length, the redundancy is still found from the others
+        encoded[1 + 5] ^= 1;
+        let last = encoded.len() - 1;
+        encoded[last] ^= 1;
+
+        assert_eq!(decode(&encoded), Ok((data.clone(), 2)));
+
+        // more than the 2 parity shards can repair
+        encoded[1 + 3 * FRAME] ^= 0xff;
+        assert_eq!(decode(&encoded), Err(FecError::TooManyErrors));
+
+        // a shard cut short is an erased one as well
+        let encoded = encode(&data, 25);
+        assert_eq!(decode(&encoded[..last]), Ok((data.clone(), 1)));
+
+        assert_eq!(decode(&[header::FRAMED]), Err(FecError::Truncated));
+        assert_eq!(
+            decode(&[header::FRAMED, 1, 2, 3]),
+            Err(FecError::TooManyErrors)
+        );
+    }
+}
@@ -0,0 +1,353 @@
+/// Current header format version, which adds error correction
+pub const VERSION: u8 = 2;
+
+/// Size of an encoded header in bytes, without error correction
+pub const SIZE: usize = 2;
+
+/// First byte of error corrected streams in place of the header, which is protected inside the
+/// frames following it, see [`crate::fec`]
+pub const FRAMED: u8 = ((VERSION + 1) << VERSION_SHIFT) | SENTINEL;
+
+/// Size of an encoded header in bytes, with error correction
+pub const FEC_SIZE: usize = 3;
+
+// first byte layout, the version above the sentinel bits
+const SENTINEL: u8 = 0b0000_0110;
+const VERSION_SHIFT: u8 = 3;
+
+// flags byte layout
+const MODE_MASK: u8 = 0b0000_0011;
+const COMPRESSION_SHIFT: u8 = 2;
+const COMPRESSION_MASK: u8 = 0b0001_1100;
+const INNER_COMPRESSION: u8 = 4;
+const INTEGRITY_SHIFT: u8 = 5;
+const INTEGRITY_MASK: u8 = 0b0110_0000;
+const SIGNED_FLAG: u8 = 0b1000_0000;
+
+/// How the payload following the header is protected
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Plaintext,
+    Encrypted,
+    /// Encrypted for X25519 public keys
+    Recipients,
+}
+
+/// Compression algorithm the secret was packed with
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Compression {
+    Deflate,
+    /// Kept as is
+    Store,
+    /// ASCII text packed into 7 bits per char
+    SmallText,
+    /// Codebook of common fragments, the payload starts with the id of the dictionary
+    Dictionary,
+}
+
+impl Compression {
+    /// Id of the compression, in the header or the first byte of an encrypted payload
+    pub fn id(&self) -> u8 {
+        match self {
+            Compression::Deflate => 0,
+            Compression::Store => 1,
+            Compression::SmallText => 2,
+            Compression::Dictionary => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Compression::Deflate),
+            1 => Some(Compression::Store),
+            2 => Some(Compression::SmallText),
+            3 => Some(Compression::Dictionary),
+            _ => None,
+        }
+    }
+}
+
+/// Integrity protection applied to the payload
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Integrity {
+    None,
+    Hmac,
+    Aead,
+}
+
+/// Self-describing header which is prepended to the data before it's zwc encoded
+///
+/// Layout:
+/// - byte 0: bits 1-2 set, bits 3-7 format version
+/// - byte 1: bits 0-1 mode, bits 2-4 compression or 4 if it's encrypted, bits 5-6 integrity,
+///   bit 7 signed
+/// - byte 2: error correction redundancy in percent, from version 2 on, which is only written
+///   inside the frames after [`FRAMED`]
+///
+/// Headers without error correction are still written as version 1.
+///
+/// Bits 1-2 of the first byte are the deflate block type which doesn't exist, so it's never
+/// the start of a headerless stream from 0.1.6 and before. Those start with deflated data, or
+/// the integrity flag of an encrypted payload.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub version: u8,
+    pub mode: Mode,
+    /// `None` if the first byte of the decrypted payload names it, so the choice doesn't give
+    /// away what kind of secret was encrypted
+    pub compression: Option<Compression>,
+    pub integrity: Integrity,
+    /// Payload is preceded by an Ed25519 signature block
+    pub signed: bool,
+    /// Parity shards protecting the rest of the stream, in percent of its shards, see [`crate::fec`]
+    pub redundancy: u8,
+}
+
+impl Header {
+    /// Create a header for the current format version
+    pub fn new(mode: Mode, compression: Option<Compression>, integrity: Integrity) -> Self {
+        Self {
+            version: 1,
+            mode,
+            compression,
+            integrity,
+            signed: false,
+            redundancy: 0,
+        }
+    }
+
+    /// Mark the payload as signed
+    pub fn signed(mut self, signed: bool) -> Self {
+        self.signed = signed;
+        self
+    }
+
+    /// Protect the rest of the stream with error correction, 0 for none
+    pub fn redundancy(mut self, redundancy: u8) -> Self {
+        self.redundancy = redundancy;
+        self.version = if redundancy > 0 { VERSION } else { 1 };
+        self
+    }
+
+    /// Size of the encoded header in bytes
+    pub fn size(&self) -> usize {
+        if self.version >= 2 {
+            FEC_SIZE
+        } else {
+            SIZE
+        }
+    }
+
+    /// Encode the header into bytes
+    pub fn encode(&self) -> Vec<u8> {
+        let mode = match self.mode {
+            Mode::Plaintext => 0,
+            Mode::Encrypted => 1,
+            Mode::Recipients => 2,
+        };
+
+        let compression = self
+            .compression
+            .map_or(INNER_COMPRESSION, |compression| compression.id());
+
+        let integrity = match self.integrity {
+            Integrity::None => 0,
+            Integrity::Hmac => 1,
+            Integrity::Aead => 2,
+        };
+
+        let mut flags = mode | (compression << COMPRESSION_SHIFT) | (integrity << INTEGRITY_SHIFT);
+        if self.signed {
+            flags |= SIGNED_FLAG;
+        }
+
+        let mut header = vec![(self.version << VERSION_SHIFT) | SENTINEL, flags];
+        if self.version >= 2 {
+            header.push(self.redundancy);
+        }
+
+        header
+    }
+
+    /// Decode a header from the start of a binary stream
+    ///
+    /// Returns the header and the rest of the stream after it, or [`HeaderError::Missing`] for
+    /// headerless streams from 0.1.6 and before
+    pub fn decode(data: &[u8]) -> Result<(Self, &[u8]), HeaderError> {
+        match data.first() {
+            Some(first) if first & !(u8::MAX << VERSION_SHIFT) == SENTINEL => (),
+            Some(_) => return Err(HeaderError::Missing),
+            None => return Err(HeaderError::Truncated),
+        }
+
+        let [first, flags, rest @ ..] = data else {
+            return Err(HeaderError::Truncated);
+        };
+
+        let version = first >> VERSION_SHIFT;
+        if version == 0 || version > VERSION {
+            return Err(HeaderError::UnsupportedVersion(version));
+        }
+
+        let mode = match flags & MODE_MASK {
+            0 => Mode::Plaintext,
+            1 => Mode::Encrypted,
+            2 => Mode::Recipients,
+            _ => return Err(HeaderError::Invalid),
+        };
+
+        let compression = match (flags & COMPRESSION_MASK) >> COMPRESSION_SHIFT {
+            INNER_COMPRESSION if mode == Mode::Plaintext => return Err(HeaderError::Invalid),
+            INNER_COMPRESSION => None,
+            id => Some(Compression::from_id(id).ok_or(HeaderError::Invalid)?),
+        };
+
+        let integrity = match (flags & INTEGRITY_MASK) >> INTEGRITY_SHIFT {
+            0 => Integrity::None,
+            1 => Integrity::Hmac,
+            2 => Integrity::Aead,
+            _ => return Err(HeaderError::Invalid),
+        };
+
+        let (redundancy, rest) = match rest {
+            _ if version < 2 => (0, rest),
+            [0, ..] => return Err(HeaderError::Invalid),
+            [redundancy, rest @ ..] => (*redundancy, rest),
+            [] => return Err(HeaderError::Truncated),
+        };
+
+        let header = Self {
+            version,
+            mode,
+            compression,
+            integrity,
+            signed: flags & SIGNED_FLAG != 0,
+            redundancy,
+        };
+
+        Ok((header, rest))
+    }
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum HeaderError {
+    #[error("Stream has no header")]
+    Missing,
+    #[error("Header is truncated")]
+    Truncated,
+    #[error("Unsupported format version {0}")]
+    UnsupportedVersion(u8),
+    #[error("Header contains invalid flags")]
+    Invalid,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let header = Header::new(Mode::Encrypted, None, Integrity::Hmac).signed(true);
+        let mut data = header.encode().to_vec();
+        data.extend([1, 2, 3]);
+
+        let (decoded, rest) = Header::decode(&data).unwrap();
+
+        assert_eq!(decoded, header);
+        assert_eq!(rest, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_compression() {
+        for compression in [
+            Compression::Deflate,
+            Compression::Store,
+            Compression::SmallText,
+            Compression::Dictionary,
+        ] {
+            let header = Header::new(Mode::Plaintext, Some(compression), Integrity::None);
+            assert_eq!(Header::decode(&header.encode()), Ok((header, &[][..])));
+
+            let header = Header::new(Mode::Recipients, Some(compression), Integrity::Aead);
+            assert_eq!(Header::decode(&header.encode()), Ok((header, &[][..])));
+        }
+
+        // plaintext has no encrypted payload to name the compression
+        let data = Header::new(Mode::Encrypted, None, Integrity::None).encode();
+        assert_eq!(data[1], INNER_COMPRESSION << COMPRESSION_SHIFT | 1);
+        assert_eq!(
+            Header::decode(&[data[0], data[1] & !MODE_MASK]),
+            Err(HeaderError::Invalid)
+        );
+    }
+
+    #[test]
+    fn test_redundancy() {
+        let header = Header::new(Mode::Plaintext, Some(Compression::Deflate), Integrity::None);
+        assert_eq!(header.encode().len(), SIZE);
+
+        let header = header.redundancy(25);
+        let mut data = header.encode();
+        assert_eq!(data, [first(VERSION), 0, 25]);
+        data.push(1);
+
+        assert_eq!(Header::decode(&data), Ok((header, &[1][..])));
+        assert_eq!(Header::decode(&data[..2]), Err(HeaderError::Truncated));
+        assert_eq!(
+            Header::decode(&[first(VERSION), 0, 0]),
+            Err(HeaderError::Invalid)
+        );
+    }
+
+    /// First byte of a header of `version`
+    fn first(version: u8) -> u8 {
+        (version << VERSION_SHIFT) | SENTINEL
+    }
+
+    #[test]
+    fn test_truncated() {
+        assert_eq!(Header::decode(&[]), Err(HeaderError::Truncated));
+        assert_eq!(
+            Header::decode(&[first(VERSION)]),
+            Err(HeaderError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_missing() {
+        // encrypted payloads start with their integrity flag, deflate data with a block type
+        for data in [
+            &[0, 1, 2][..],
+            &[1, 2],
+            &[0b011, 2],
+            &[0b101, 2],
+            &[first(1) | 1, 0],
+        ] {
+            assert_eq!(Header::decode(data), Err(HeaderError::Missing));
+        }
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        assert_eq!(
+            Header::decode(&[first(VERSION + 1), 0]),
+            Err(HeaderError::UnsupportedVersion(VERSION + 1))
+        );
+        assert_eq!(
+            Header::decode(&[first(0), 0]),
+            Err(HeaderError::UnsupportedVersion(0))
+        );
+    }
+
+    #[test]
+    fn test_invalid_flags() {
+        assert_eq!(
+            Header::decode(&[first(VERSION), 0b11]),
+            Err(HeaderError::Invalid)
+        );
+        assert_eq!(
+            Header::decode(&[first(1), 0b0001_0100]),
+            Err(HeaderError::Invalid)
+        );
+    }
+}
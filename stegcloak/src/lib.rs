@@ -14,9 +14,27 @@
 //!
 //! - Allows you to invisibly hide your secret inside regular text
 //! - Protect your secret with password and HMAC integrity
-//! - Encrypts your secret with AES-256-CTR
+//! - Derives keys with PBKDF2-SHA512 or Argon2id
+//! - Hide secrets for X25519 public keys instead of a shared password
+//! - Optionally sign messages with Ed25519 to prove who cloaked them
+//! - Hide several secrets in one text, each under its own password
+//! - Detect and strip hidden data from text
+//! - Caps decompressed and decoded sizes against decompression bombs in untrusted text
+//! - Wipes keys and plaintext buffers from memory once dropped, with `SecretString` inputs for secrets and passwords
+//! - Streaming hide/reveal over `Read`/`Write` with bounded memory
+//! - Encrypts your secret with AES-256-CTR, or authenticated AES-256-GCM / ChaCha20-Poly1305
 //! - Uses 6 invisible unicode characters that work in many places such as Gmail, WhatsApp, Telegram, Facebook, and more!
-//! - Uses compression to minimize size of payload.
+//! - Uses compression to minimize size of payload, picking deflate, 7 bit ASCII packing, a codebook for short English text and URLs (or your own) or none, whichever is smallest.
+//! - Optional radix codec using all 6 characters for ~15% shorter output
+//! - Optional variation selector codec with one invisible character per byte
+//! - Optional tag character codec carrying 7 bits per character
+//! - Optional whitespace codec hiding bits in lookalike spaces, which survives stripping of invisible characters
+//! - Optional homoglyph codec hiding bits in Latin letters swapped for Cyrillic or Greek lookalikes, with capacity estimation for a cover text
+//! - Placement strategies which spread the stream in fragments across the spaces, evenly or keyed by the password, or append it to the end
+//! - Optional Reed-Solomon error correction with configurable redundancy, which also survives dropped or added characters, reporting how many corrupted shards were repaired
+//! - Estimates how many chars, UTF-8 bytes and UTF-16 units a secret adds, and can refuse to exceed a platform's length limit
+//! - Alphabet profiles (max compatibility, joiners only, variation selectors, tag characters) for platforms that strip different characters, detected automatically on reveal
+//! - Tunable key derivation, compression, placement and alphabet through the `StegCloak` builder
 //! - Wasm compatible
 //! - Fast🦀!
 //!   - Can hide the entire wikipedia source in ~201468 characters taking ~3.5ms in plaintext mode and ~7ms in encrypt mode
@@ -48,16 +66,116 @@
 //! WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE
 //! OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
+mod builder;
 pub mod codec;
 pub mod compact;
 pub mod crypto;
+mod detect;
+mod estimate;
+pub mod fec;
+pub mod header;
 
+pub use builder::{Corrected, Placement, Signed, StegCloak};
+pub use detect::{detect, detect_with, strip, Detection};
+pub use estimate::{estimate, Estimate, Limit};
+pub use secrecy::{ExposeSecret, SecretString};
+
+use builder::{to_string, Key};
 use codec::CodecError;
 use compact::DeCompressError;
 use crypto::DeEncryptError;
+use fec::FecError;
+use header::{HeaderError, Mode};
+
+/// Reveal a secret inside a message
+///
+/// The message header records whether the secret was encrypted, so the password is only
+/// used when it's needed.
+///
+/// # Arguments
+///
+/// * `message` - The visible text everybody else sees
+/// * `password` - The password to decrypt the secret with, if it was encrypted
+///
+/// # Examples
+///
+/// ```rust
+///     stegcloak::reveal("cover text", Some("mypassword")); // -> "mysecret"
+/// ```
+///
+pub fn reveal(message: impl AsRef<str>, password: Option<&str>) -> Result<String, StegError> {
+    to_string(reveal_bytes(message, password)?)
+}
+
+/// Reveal a binary secret inside a message
+///
+/// The message header records whether the secret was encrypted, so the password is only
+/// used when it's needed.
+///
+/// # Arguments
+///
+/// * `message` - The visible text everybody else sees
+/// * `password` - The password to decrypt the secret with, if it was encrypted
+///
+/// # Examples
+///
+/// ```rust
+///     stegcloak::reveal_bytes("cover text", None); // -> [0xde, 0xad, 0xbe, 0xef]
+/// ```
+///
+pub fn reveal_bytes(
+    message: impl AsRef<str>,
+    password: Option<&str>,
+) -> Result<Vec<u8>, StegError> {
+    StegCloak::new().reveal_bytes(message, password)
+}
+
+/// Reveal every secret inside a message which the password opens
+///
+/// A message can carry several secrets, each hidden with [`StegCloak::hide_many`] under its
+/// own password. Plaintext secrets open regardless of the password.
+///
+/// # Arguments
+///
+/// * `message` - The visible text everybody else sees
+/// * `password` - The password to decrypt the secrets with
+///
+/// # Examples
+///
+/// ```rust
+///     stegcloak::reveal_all("cover text", Some("mypassword")); // -> ["mysecret"]
+/// ```
+///
+pub fn reveal_all(
+    message: impl AsRef<str>,
+    password: Option<&str>,
+) -> Result<Vec<String>, StegError> {
+    StegCloak::new().reveal_all(message, password)
+}
+
+/// Reveal every binary secret inside a message which the password opens
+///
+/// # Arguments
+///
+/// * `message` - The visible text everybody else sees
+/// * `password` - The password to decrypt the secrets with
+///
+/// # Examples
+///
+/// ```rust
+///     stegcloak::reveal_all_bytes("cover text", None); // -> [[0xde, 0xad, 0xbe, 0xef]]
+/// ```
+///
+pub fn reveal_all_bytes(
+    message: impl AsRef<str>,
+    password: Option<&str>,
+) -> Result<Vec<Vec<u8>>, StegError> {
+    StegCloak::new().reveal_all_bytes(message, password)
+}
 
 pub mod encrypt {
-    use super::StegError;
+    use super::{to_string, Key, Mode, Signed, StegCloak, StegError};
+    use crate::crypto::signature::SigningKey;
 
     /// Hide an encrypted secret inside a message
     ///
@@ -79,12 +197,38 @@ pub mod encrypt {
         password: impl AsRef<str>,
         integrity: bool,
         message: impl AsRef<str>,
+    ) -> Result<String, StegError> {
+        hide_bytes(secret.as_ref(), password, integrity, message)
+    }
+
+    /// Hide an encrypted binary secret inside a message
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The bytes you want to hide
+    /// * `password` - The password to encrypt the secret with
+    /// * `integrity` - Create message that protects against tampering
+    /// * `message` - The visible text everybody else will see
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    ///     stegcloak::encrypt::hide_bytes([0xde, 0xad, 0xbe, 0xef], "mypassword", false, "cover text"); // -> "cover text"
+    /// ```
+    ///
+    pub fn hide_bytes(
+        secret: impl AsRef<[u8]>,
+        password: impl AsRef<str>,
+        integrity: bool,
+        message: impl AsRef<str>,
     ) -> Result<String, StegError> {
         let secret = secret.as_ref();
         let password = password.as_ref();
         let message = message.as_ref();
 
-        super::_hide(true, integrity, secret, password, message)
+        StegCloak::new()
+            .integrity(integrity)
+            .hide_bytes(secret, Some(password), message)
     }
 
     /// Reveal an encrypted secret inside a message
@@ -103,15 +247,207 @@ pub mod encrypt {
         password: impl AsRef<str>,
         message: impl AsRef<str>,
     ) -> Result<String, StegError> {
+        to_string(reveal_bytes(password, message)?)
+    }
+
+    /// Reveal an encrypted binary secret inside a message
+    ///
+    /// # Arguments
+    ///
+    /// * `password` - The password to decrypt the secret with
+    /// * `message` - The visible text everybody else sees
+    ///
+    /// # Examples
+    /// ```rust
+    ///     stegcloak::encrypt::reveal_bytes("mypassword", "cover text"); // -> [0xde, 0xad, 0xbe, 0xef]
+    /// ```
+    ///
+    pub fn reveal_bytes(
+        password: impl AsRef<str>,
+        message: impl AsRef<str>,
+    ) -> Result<Vec<u8>, StegError> {
+        let password = password.as_ref();
+        let message = message.as_ref();
+
+        StegCloak::new()._reveal(
+            Some(Mode::Encrypted),
+            Some(Key::Password(password)),
+            message,
+        )
+    }
+
+    /// Hide an encrypted secret inside a message, signed with `key`
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The secret you want to hide
+    /// * `password` - The password to encrypt the secret with
+    /// * `integrity` - Create message that protects against tampering
+    /// * `key` - The key to sign the message with
+    /// * `message` - The visible text everybody else will see
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    ///     let key = stegcloak::crypto::signature::generate();
+    ///     stegcloak::encrypt::hide_signed("mysecret", "mypassword", false, &key, "cover text"); // -> "cover text"
+    /// ```
+    ///
+    pub fn hide_signed(
+        secret: impl AsRef<str>,
+        password: impl AsRef<str>,
+        integrity: bool,
+        key: &SigningKey,
+        message: impl AsRef<str>,
+    ) -> Result<String, StegError> {
+        StegCloak::new()
+            .integrity(integrity)
+            .signing_key(key.clone())
+            .hide(secret, Some(password.as_ref()), message)
+    }
+
+    /// Reveal a signed encrypted secret inside a message, along with its verified signer
+    ///
+    /// # Arguments
+    ///
+    /// * `password` - The password to decrypt the secret with
+    /// * `message` - The visible text everybody else sees
+    ///
+    /// # Examples
+    /// ```rust
+    ///     stegcloak::encrypt::reveal_signed("mypassword", "cover text"); // -> Signed { secret: "mysecret", signer }
+    /// ```
+    ///
+    pub fn reveal_signed(
+        password: impl AsRef<str>,
+        message: impl AsRef<str>,
+    ) -> Result<Signed<String>, StegError> {
         let password = password.as_ref();
         let message = message.as_ref();
 
-        super::_reveal(true, password, message)
+        let Signed { secret, signer } = StegCloak::new()._reveal_signed(
+            Some(Mode::Encrypted),
+            Some(Key::Password(password)),
+            message,
+        )?;
+
+        Ok(Signed {
+            secret: to_string(secret)?,
+            signer,
+        })
+    }
+}
+
+pub mod recipient {
+    use super::{to_string, Key, Mode, StegCloak, StegError};
+    use crate::crypto::recipient::{Identity, Recipient};
+
+    /// Hide a secret inside a message, encrypted for X25519 public keys
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The secret you want to hide
+    /// * `recipients` - The public keys to encrypt the secret for
+    /// * `message` - The visible text everybody else will see
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    ///     use stegcloak::crypto::recipient::Identity;
+    ///
+    ///     let identity = Identity::generate();
+    ///     stegcloak::recipient::hide("mysecret", &[identity.to_public()], "cover text"); // -> "cover text"
+    /// ```
+    ///
+    pub fn hide(
+        secret: impl AsRef<str>,
+        recipients: &[Recipient],
+        message: impl AsRef<str>,
+    ) -> Result<String, StegError> {
+        hide_bytes(secret.as_ref(), recipients, message)
+    }
+
+    /// Hide a binary secret inside a message, encrypted for X25519 public keys
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The bytes you want to hide
+    /// * `recipients` - The public keys to encrypt the secret for
+    /// * `message` - The visible text everybody else will see
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    ///     use stegcloak::crypto::recipient::Identity;
+    ///
+    ///     let identity = Identity::generate();
+    ///     stegcloak::recipient::hide_bytes([0xde, 0xad, 0xbe, 0xef], &[identity.to_public()], "cover text"); // -> "cover text"
+    /// ```
+    ///
+    pub fn hide_bytes(
+        secret: impl AsRef<[u8]>,
+        recipients: &[Recipient],
+        message: impl AsRef<str>,
+    ) -> Result<String, StegError> {
+        StegCloak::new().hide_bytes_for_recipients(secret, recipients, message)
+    }
+
+    /// Reveal a secret which was hidden for the identity's public key
+    ///
+    /// # Arguments
+    ///
+    /// * `identity` - The secret key matching one of the recipients
+    /// * `message` - The visible text everybody else sees
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    ///     use stegcloak::crypto::recipient::Identity;
+    ///
+    ///     let identity = Identity::generate();
+    ///     stegcloak::recipient::reveal_with_identity(&identity, "cover text"); // -> "mysecret"
+    /// ```
+    ///
+    pub fn reveal_with_identity(
+        identity: &Identity,
+        message: impl AsRef<str>,
+    ) -> Result<String, StegError> {
+        to_string(reveal_bytes_with_identity(identity, message)?)
+    }
+
+    /// Reveal a binary secret which was hidden for the identity's public key
+    ///
+    /// # Arguments
+    ///
+    /// * `identity` - The secret key matching one of the recipients
+    /// * `message` - The visible text everybody else sees
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    ///     use stegcloak::crypto::recipient::Identity;
+    ///
+    ///     let identity = Identity::generate();
+    ///     stegcloak::recipient::reveal_bytes_with_identity(&identity, "cover text"); // -> [0xde, 0xad, 0xbe, 0xef]
+    /// ```
+    ///
+    pub fn reveal_bytes_with_identity(
+        identity: &Identity,
+        message: impl AsRef<str>,
+    ) -> Result<Vec<u8>, StegError> {
+        let message = message.as_ref();
+
+        StegCloak::new()._reveal(
+            Some(Mode::Recipients),
+            Some(Key::Identity(identity)),
+            message,
+        )
     }
 }
 
 pub mod plaintext {
-    use super::StegError;
+    use super::{to_string, Mode, Signed, StegCloak, StegError};
+    use crate::crypto::signature::SigningKey;
 
     /// Hide a plaintext secret inside a message
     ///
@@ -129,10 +465,32 @@ pub mod plaintext {
     /// ```
     ///
     pub fn hide(secret: impl AsRef<str>, message: impl AsRef<str>) -> Result<String, StegError> {
+        hide_bytes(secret.as_ref(), message)
+    }
+
+    /// Hide a plaintext binary secret inside a message
+    ///
+    /// Warn: The secret will be in plaintext! Anyone can freely decode it!
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The bytes you want to hide
+    /// * `message` - The visible text everybody else will see
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    ///     stegcloak::plaintext::hide_bytes([0xde, 0xad, 0xbe, 0xef], "cover text"); // -> "cover text"
+    /// ```
+    ///
+    pub fn hide_bytes(
+        secret: impl AsRef<[u8]>,
+        message: impl AsRef<str>,
+    ) -> Result<String, StegError> {
         let secret = secret.as_ref();
         let message = message.as_ref();
 
-        super::_hide(false, false, secret, "", message)
+        StegCloak::new().hide_bytes(secret, None, message)
     }
 
     /// Reveal a plaintext secret inside a message
@@ -148,52 +506,77 @@ pub mod plaintext {
     /// ```
     ///
     pub fn reveal(message: impl AsRef<str>) -> Result<String, StegError> {
+        to_string(reveal_bytes(message)?)
+    }
+
+    /// Reveal a plaintext binary secret inside a message
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The visible text everybody else sees
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    ///     stegcloak::plaintext::reveal_bytes("cover text"); // -> [0xde, 0xad, 0xbe, 0xef]
+    /// ```
+    ///
+    pub fn reveal_bytes(message: impl AsRef<str>) -> Result<Vec<u8>, StegError> {
         let message = message.as_ref();
 
-        super::_reveal(false, "", message)
+        StegCloak::new()._reveal(Some(Mode::Plaintext), None, message)
     }
-}
 
-fn _hide(
-    encrypt: bool,
-    integrity: bool,
-    secret: &str,
-    password: &str,
-    message: &str,
-) -> Result<String, StegError> {
-    // minimum 1 space required
-    let Some(space_pos) = message.find(' ') else {
-        return Err(StegError::SpaceRequired);
-    };
-
-    let secret = compact::compress(secret)?;
-    let data = if encrypt {
-        crypto::encrypt(password, &secret, integrity)?
-    } else {
-        secret
-    };
-
-    let encoded = codec::encode(&data);
-
-    let mut message = message.to_owned();
-    message.insert_str(space_pos + 1, &encoded);
-
-    Ok(message)
-}
-
-fn _reveal(encrypt: bool, password: &str, message: &str) -> Result<String, StegError> {
-    if !message.contains(' ') {
-        return Err(StegError::SpaceRequired);
+    /// Hide a plaintext secret inside a message, signed with `key`
+    ///
+    /// Warn: The secret will be in plaintext! Anyone can freely decode it!
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The secret you want to hide
+    /// * `key` - The key to sign the message with
+    /// * `message` - The visible text everybody else will see
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    ///     let key = stegcloak::crypto::signature::generate();
+    ///     stegcloak::plaintext::hide_signed("mysecret", &key, "cover text"); // -> "cover text"
+    /// ```
+    ///
+    pub fn hide_signed(
+        secret: impl AsRef<str>,
+        key: &SigningKey,
+        message: impl AsRef<str>,
+    ) -> Result<String, StegError> {
+        StegCloak::new()
+            .signing_key(key.clone())
+            .hide(secret, None, message)
     }
 
-    let decoded = codec::decode(message)?;
-    let data = if encrypt {
-        crypto::decrypt(password, &decoded)?
-    } else {
-        decoded
-    };
+    /// Reveal a signed plaintext secret inside a message, along with its verified signer
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The visible text everybody else sees
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    ///     stegcloak::plaintext::reveal_signed("cover text"); // -> Signed { secret: "mysecret", signer }
+    /// ```
+    ///
+    pub fn reveal_signed(message: impl AsRef<str>) -> Result<Signed<String>, StegError> {
+        let message = message.as_ref();
+
+        let Signed { secret, signer } =
+            StegCloak::new()._reveal_signed(Some(Mode::Plaintext), None, message)?;
 
-    Ok(compact::decompress(&data)?)
+        Ok(Signed {
+            secret: to_string(secret)?,
+            signer,
+        })
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -206,4 +589,224 @@ pub enum StegError {
     DeEncryptError(#[from] DeEncryptError),
     #[error("Codec failed: {0}")]
     CodecError(#[from] CodecError),
+    #[error("Invalid header: {0}")]
+    HeaderError(#[from] HeaderError),
+    #[error("Error correction failed: {0}")]
+    FecError(#[from] FecError),
+    #[error("Secret is encrypted, a password is required")]
+    PasswordRequired,
+    #[error("Secret is not encrypted")]
+    NotEncrypted,
+    #[error("Secret is encrypted for recipients, an identity is required")]
+    IdentityRequired,
+    #[error("Secret is not signed")]
+    NotSigned,
+    #[error(
+        "Streaming only supports AES-256-CTR without signing, error correction or a length limit, \
+         no whitespace or homoglyph codec, and no spread or scattered placement"
+    )]
+    StreamingUnsupported,
+    #[error("Message with the hidden secret is {length} long, over the limit of {limit}")]
+    LimitExceeded { length: usize, limit: Limit },
+    #[error("IoError: {0:?}")]
+    IoError(#[source] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plaintext_bytes() {
+        let hidden = plaintext::hide_bytes([0, 159, 146, 150, 255], "cover text").unwrap();
+        let secret = plaintext::reveal_bytes(hidden).unwrap();
+
+        assert_eq!(secret, &[0, 159, 146, 150, 255]);
+    }
+
+    #[test]
+    fn test_encrypt_bytes() {
+        let hidden =
+            encrypt::hide_bytes([0, 159, 146, 150, 255], "123", true, "cover text").unwrap();
+        let secret = encrypt::reveal_bytes("123", hidden).unwrap();
+
+        assert_eq!(secret, &[0, 159, 146, 150, 255]);
+    }
+
+    #[test]
+    fn test_reveal_dispatch() {
+        let hidden = plaintext::hide("mysecret", "cover text").unwrap();
+        assert_eq!(reveal(&hidden, None).unwrap(), "mysecret");
+        assert_eq!(reveal(&hidden, Some("123")).unwrap(), "mysecret");
+
+        let hidden = encrypt::hide("mysecret", "123", true, "cover text").unwrap();
+        assert_eq!(reveal(&hidden, Some("123")).unwrap(), "mysecret");
+        assert!(matches!(
+            reveal(&hidden, None),
+            Err(StegError::PasswordRequired)
+        ));
+    }
+
+    #[test]
+    fn test_reveal_unversioned() {
+        // written by 0.1.6, which had no header
+        let plain =
+            "cover \u{200c}\u{2061}\u{2062}\u{2061}\u{200c}\u{2062}\u{200d}\u{2062}\u{2064}\
+            \u{200c}\u{2062}\u{2061}\u{200c}\u{2061}\u{2062}\u{200c}\u{200d}\u{200d}\u{2062}\
+            \u{200c}\u{200d}\u{2061}\u{2062}\u{2061}\u{200c}\u{2064}\u{200c}\u{200d}\u{200d}\
+            \u{2062}\u{2061}\u{200c}\u{200d}\u{2063}\u{2063}\u{2063}\u{200c}text";
+        let integrity =
+            "cover \u{200d}\u{2061}\u{200d}\u{200c}\u{200c}\u{200c}\u{200d}\u{2062}\u{2062}\
+            \u{2064}\u{2062}\u{200d}\u{2062}\u{2062}\u{2061}\u{200c}\u{200d}\u{2062}\u{200c}\
+            \u{200c}\u{2064}\u{200c}\u{2061}\u{2063}\u{200d}\u{2061}\u{200c}\u{2061}\u{2062}\
+            \u{200c}\u{2062}\u{2063}\u{200c}\u{2061}\u{200c}\u{200c}\u{2064}\u{2061}\u{200c}\
+            \u{2061}\u{200c}\u{2061}\u{2063}\u{200d}\u{200c}\u{200c}\u{2062}\u{2062}\u{2062}\
+            \u{200d}\u{2062}\u{2061}\u{2062}\u{2063}\u{200c}\u{200d}\u{2061}\u{200c}\u{2062}\
+            \u{2061}\u{200d}\u{200c}\u{200c}\u{2064}\u{2062}\u{200c}\u{2062}\u{2062}\u{2061}\
+            \u{2062}\u{2064}\u{200d}\u{2061}\u{200c}\u{2062}\u{200d}\u{200c}\u{2061}\u{200c}\
+            \u{200c}\u{2061}\u{200c}\u{2064}\u{2062}\u{200c}\u{200d}\u{2061}\u{200c}\u{200d}\
+            \u{2062}\u{200c}\u{2063}\u{200d}\u{200c}\u{2061}\u{2062}\u{2061}\u{200c}\u{200d}\
+            \u{2062}\u{2061}\u{2062}\u{2064}\u{2063}\u{200c}\u{200d}\u{2061}\u{200c}\u{200d}\
+            \u{2061}\u{200d}\u{200c}\u{2061}\u{2062}\u{2064}\u{2061}\u{200d}\u{2062}\u{2061}\
+            \u{2063}\u{2062}\u{2061}\u{200d}\u{200c}\u{2061}\u{2062}\u{200d}\u{200c}\u{2063}\
+            \u{2063}\u{200d}\u{200c}\u{2063}\u{200c}\u{2063}\u{200d}\u{200c}\u{2061}\u{200d}\
+            \u{2061}\u{200c}\u{2061}\u{200d}\u{2062}\u{200c}\u{2061}\u{200d}\u{2062}\u{2064}\
+            \u{200c}\u{200d}\u{2061}\u{2063}\u{200d}\u{2061}\u{2063}\u{2062}\u{2062}\u{200c}\
+            \u{2063}\u{200d}\u{2061}\u{2063}\u{200d}\u{2062}\u{2063}\u{200c}\u{2061}\u{200d}\
+            \u{200c}\u{2063}\u{2063}\u{2061}\u{2062}\u{200d}\u{2061}\u{2062}\u{200c}\u{200d}\
+            \u{2061}\u{200c}\u{200c}\u{200d}\u{2062}\u{2064}\u{200c}\u{2062}\u{200c}\u{2062}\
+            \u{200d}\u{2062}\u{200d}\u{2061}\u{2063}\u{200c}\u{200c}\u{200c}\u{200c}\u{2062}\
+            \u{2062}\u{2061}\u{200c}\u{200c}\u{200d}\u{2061}\u{200d}\u{200c}\u{2062}\u{2063}\
+            \u{200d}\u{2061}\u{200d}\u{2062}\u{200d}\u{2062}\u{2062}\u{200d}\u{2062}\u{200d}\
+            \u{2064}\u{2064}\u{2062}\u{2062}\u{2062}\u{2064}\u{2064}\u{200c}\u{2061}\u{2062}\
+            \u{2062}\u{2061}\u{2063}\u{200c}\u{2062}\u{200d}\u{200c}\u{2061}\u{200d}\u{2061}\
+            \u{200c}\u{200c}\u{200c}\u{2061}\u{200c}\u{2062}\u{2061}\u{200c}\u{200c}\u{200d}\
+            \u{200c}\u{2062}\u{2063}\u{200d}\u{2064}\u{200d}\u{200c}\u{200d}\u{2061}\u{2062}\
+            \u{2062}\u{2061}\u{2062}\u{200c}\u{2061}\u{200d}\u{200c}\u{2061}\u{200c}\u{2061}\
+            \u{200d}\u{200c}\u{2062}\u{200c}\u{200c}\u{200c}\u{2062}\u{2063}\u{200d}\u{2064}\
+            \u{2063}\u{2061}\u{2062}\u{200d}\u{2061}\u{200c}\u{2062}\u{2063}\u{2061}\u{200d}\
+            \u{200c}\u{200c}\u{2061}\u{200d}\u{2064}\u{200d}\u{2061}\u{200c}\u{200c}\u{200d}\
+            \u{2061}\u{200c}\u{2061}\u{200c}\u{2062}\u{2063}\u{2062}\u{2061}\u{2062}\u{200c}\
+            \u{200d}\u{200c}\u{2064}\u{200c}\u{200c}\u{200c}\u{2063}\u{2064}\u{2062}\u{200c}\
+            \u{2062}\u{200d}\u{2061}\u{2063}\u{2061}\u{2062}\u{2063}\u{200c}\u{200c}\u{2063}\
+            \u{200c}\u{200c}\u{2062}\u{200c}\u{2062}\u{2064}\u{200d}\u{2064}\u{200d}\u{200c}\
+            \u{2062}\u{200c}\u{2063}\u{2062}\u{200d}\u{2061}\u{200d}text";
+        let encrypted =
+            "cover \u{200d}\u{2061}\u{200c}\u{200c}\u{200c}\u{200c}\u{200d}\u{2064}\u{200c}\
+            \u{200c}\u{2062}\u{200c}\u{2062}\u{200c}\u{2061}\u{200d}\u{200c}\u{2061}\u{200d}\
+            \u{2062}\u{200d}\u{2061}\u{2062}\u{200d}\u{2061}\u{2062}\u{2064}\u{2062}\u{200d}\
+            \u{200c}\u{2061}\u{2062}\u{200d}\u{200c}\u{200c}\u{2061}\u{2062}\u{2061}\u{2062}\
+            \u{200d}\u{200c}\u{2061}\u{200c}\u{2061}\u{2062}\u{2062}\u{2061}\u{2062}\u{200c}\
+            \u{2061}\u{2062}\u{200d}\u{2062}\u{2062}\u{2062}\u{200c}\u{200c}\u{2062}\u{200d}\
+            \u{2062}\u{2062}\u{200c}\u{2061}\u{2062}\u{2062}\u{2062}\u{2062}\u{200d}\u{2064}\
+            \u{2062}\u{2062}\u{200d}\u{200c}\u{2061}\u{200d}\u{200c}\u{200d}\u{2062}\u{2061}\
+            \u{2062}\u{2062}\u{2062}\u{200c}\u{2062}\u{200c}\u{200c}\u{200c}\u{200c}\u{200d}\
+            \u{2062}\u{2062}\u{2062}\u{2062}\u{2061}\u{200c}\u{200c}\u{2061}\u{2062}\u{200c}\
+            \u{2061}\u{200c}\u{200c}\u{200d}\u{2062}\u{200d}\u{2062}\u{2062}\u{200d}\u{2061}\
+            \u{2063}\u{2063}\u{200d}\u{200c}\u{200d}\u{200c}\u{200d}\u{200c}\u{2062}\u{200d}\
+            \u{200c}\u{2062}\u{2062}\u{2062}\u{200d}\u{2064}\u{2061}\u{200d}\u{2062}\u{200c}\
+            \u{200d}\u{2062}\u{2063}\u{2061}\u{2062}\u{200d}\u{2062}\u{2062}\u{2061}\u{200d}\
+            \u{2061}\u{200c}\u{200c}\u{2063}\u{2061}\u{2063}\u{2062}\u{2063}\u{200d}\u{2062}\
+            \u{200d}\u{2061}\u{200d}\u{2061}\u{200c}\u{2062}\u{200c}\u{200c}\u{200d}\u{200c}\
+            \u{200d}\u{2061}\u{2063}\u{200d}\u{2061}\u{200d}\u{2061}\u{200d}\u{2062}\u{2064}\
+            \u{200c}\u{2061}\u{2062}\u{2061}\u{200d}\u{200c}\u{200d}\u{2061}\u{200d}\u{2062}\
+            \u{200d}\u{2061}\u{200d}\u{2061}\u{2062}\u{200c}\u{2063}\u{2061}\u{2063}\u{2064}\
+            \u{2062}\u{200d}\u{200c}\u{2062}\u{200c}\u{200d}\u{2064}\u{2063}\u{2064}\u{2063}\
+            \u{200d}\u{200c}\u{2063}\u{200c}\u{200c}\u{200c}\u{200c}\u{2064}\u{200c}\u{200c}\
+            \u{200c}\u{2062}\u{200c}\u{200c}\u{2064}\u{200d}\u{2061}\u{200c}\u{2061}\u{2062}\
+            \u{2062}\u{2062}\u{200d}\u{2062}\u{2061}\u{200c}\u{2063}\u{2061}\u{200c}\u{2064}\
+            \u{2061}\u{2062}\u{200d}\u{200c}\u{2061}\u{200d}\u{2061}\u{200d}\u{2061}\u{200c}\
+            \u{2061}\u{200d}\u{2061}\u{200d}\u{2062}\u{200d}\u{200c}text";
+
+        assert_eq!(plaintext::reveal(plain).unwrap(), "mysecret");
+        assert_eq!(reveal(plain, None).unwrap(), "mysecret");
+
+        let mut revealed = Vec::new();
+        StegCloak::new()
+            .reveal_stream(encrypted.as_bytes(), Some("mypassword"), &mut revealed)
+            .unwrap();
+        assert_eq!(revealed, b"mysecret");
+
+        for hidden in [integrity, encrypted] {
+            assert_eq!(encrypt::reveal("mypassword", hidden).unwrap(), "mysecret");
+            assert_eq!(reveal(hidden, Some("mypassword")).unwrap(), "mysecret");
+            assert!(matches!(
+                reveal(hidden, None),
+                Err(StegError::PasswordRequired)
+            ));
+            assert!(matches!(
+                encrypt::reveal("wrong", hidden),
+                Err(StegError::DeEncryptError(DeEncryptError::IncorrectPassword))
+            ));
+        }
+
+        assert!(matches!(
+            encrypt::reveal("mypassword", plain),
+            Err(StegError::NotEncrypted)
+        ));
+    }
+
+    #[test]
+    fn test_reveal_mode_mismatch() {
+        let hidden = plaintext::hide("mysecret", "cover text").unwrap();
+        assert!(matches!(
+            encrypt::reveal("123", &hidden),
+            Err(StegError::NotEncrypted)
+        ));
+
+        let hidden = encrypt::hide("mysecret", "123", false, "cover text").unwrap();
+        assert!(matches!(
+            plaintext::reveal(&hidden),
+            Err(StegError::PasswordRequired)
+        ));
+    }
+
+    #[test]
+    fn test_recipient_bytes() {
+        let identity = crypto::recipient::Identity::generate();
+        let hidden = recipient::hide_bytes(
+            [0, 159, 146, 150, 255],
+            &[identity.to_public()],
+            "cover text",
+        )
+        .unwrap();
+        let secret = recipient::reveal_bytes_with_identity(&identity, &hidden).unwrap();
+
+        assert_eq!(secret, &[0, 159, 146, 150, 255]);
+        assert!(matches!(
+            plaintext::reveal_bytes(&hidden),
+            Err(StegError::IdentityRequired)
+        ));
+    }
+
+    #[test]
+    fn test_signed_presets() {
+        let key = crypto::signature::generate();
+
+        let hidden = plaintext::hide_signed("mysecret", &key, "cover text").unwrap();
+        let signed = plaintext::reveal_signed(&hidden).unwrap();
+        assert_eq!(signed.secret, "mysecret");
+        assert_eq!(signed.signer, key.verifying_key());
+
+        let hidden = encrypt::hide_signed("mysecret", "123", false, &key, "cover text").unwrap();
+        let signed = encrypt::reveal_signed("123", &hidden).unwrap();
+        assert_eq!(signed.secret, "mysecret");
+        assert_eq!(signed.signer, key.verifying_key());
+
+        let hidden = encrypt::hide("mysecret", "123", false, "cover text").unwrap();
+        assert!(matches!(
+            encrypt::reveal_signed("123", &hidden),
+            Err(StegError::NotSigned)
+        ));
+    }
+
+    #[test]
+    fn test_reveal_non_utf8_string() {
+        let hidden = plaintext::hide_bytes([0, 159, 146, 150, 255], "cover text").unwrap();
+
+        assert!(matches!(
+            plaintext::reveal(hidden),
+            Err(StegError::DeCompressError(DeCompressError::InvalidUtf8))
+        ));
+    }
 }